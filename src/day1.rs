@@ -1,20 +1,16 @@
 
+use crate::helper::InputError;
+use crate::helper::parse::{pair, parse_lines};
+
 type LocationID = u32;
 
 //////////////////////////////////////////
 /// Input parsing
 //////////////////////////////////////////
 
-fn read_input<'a>(lines:impl Iterator<Item=&'a str>) -> (Vec<LocationID>, Vec<LocationID>) {
-    let mut left = Vec::new();
-    let mut right = Vec::new();
-    for line in lines {
-        let mut split = line.split("   ");
-        left.push(split.next().unwrap().parse::<LocationID>().unwrap());
-        right.push(split.next().unwrap().parse::<LocationID>().unwrap());
-        assert!(split.next().is_none());
-    }
-    (left, right)
+// "3   4"
+fn read_input(input:&str) -> Result<(Vec<LocationID>, Vec<LocationID>), InputError> {
+    Ok(parse_lines(input, |line| pair::<LocationID>(line, "   "))?.into_iter().unzip())
 }
 
 #[test]
@@ -27,9 +23,12 @@ fn test_read_input()
 1   3
 3   9
 3   3";
-    let (left, right) = read_input(input1.split("\n"));
+    let (left, right) = read_input(input1).unwrap();
     assert_eq!(left, vec![3,4,2,1,3,3]);
     assert_eq!(right, vec![4,3,5,3,9,3]);
+
+    assert_eq!(read_input("3   4\n4  3").unwrap_err().to_string(), "line 2, column 5: expected 2 parts separated by '   ', found 1");
+    assert_eq!(read_input("3   4\n4   99999999999").unwrap_err().to_string(), "line 2, column 5: '99999999999' is no u32");
 }
 
 //////////////////////////////////////////
@@ -67,7 +66,7 @@ fn test_distance() {
 1   3
 3   9
 3   3";
-    let (left, right) = read_input(input1.split("\n"));
+    let (left, right) = read_input(input1).unwrap();
     assert_eq!(calculate_total_distance(&left, &right), 11);
 }
 
@@ -96,7 +95,7 @@ fn test_similarity() {
 1   3
 3   9
 3   3";
-    let (left, right) = read_input(input1.split("\n"));
+    let (left, right) = read_input(input1).unwrap();
     assert_eq!(calculate_similarity(&left, &right), 31);
 }
 
@@ -104,16 +103,11 @@ fn test_similarity() {
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 pub fn puzzle() {
-    let file = File::open("input/day1.txt").expect("Could not open input/day1.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
-    let (left, right) = read_input(lines.iter().map( |line| line.as_str() ));
+    let input = read_file_to_string("input/day1.txt");
+    let (left, right) = expect_input(read_input(&input), "input/day1.txt", &input);
 
     let total_distance = calculate_total_distance(&left, &right);
     println!("Day 1, Part 1: Sum of distance of sorted pairs is {}", total_distance);
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use crate::maps::PixelMap;
+use crate::maps::{distance_field, PixelMap};
 use crate::maps::Position;
 use crate::maps::Direction;
 
@@ -9,44 +9,49 @@ struct Height {
     value:u32
 }
 
-impl crate::maps::FromChar for Height {
-    fn from_char(c:char) -> Self {
-        Height { value: c.to_digit(10).unwrap() }
+impl crate::maps::TryFromChar for Height {
+    fn try_from_char(c:char) -> Option<Self> {
+        c.to_digit(10).map(|value| Height { value })
     }
 }
 
 type Map = PixelMap<Height>;
 
-// list all peaks that can be reached from start_position
-fn reachable_peaks(map:&Map, start_position:Position) -> HashSet<Position> {
-    if map.at(start_position).value == 9 {
-        return HashSet::from([start_position]);
-    }
+const TRAILHEAD:Height = Height { value:0 };
 
-    let mut peaks:HashSet<Position> = HashSet::new();
-    for direction in Direction::four_directions() {
-        if let Some(next_position) = map.area.step(start_position, direction) {
-            if map.at(next_position).value == map.at(start_position).value + 1 {
-                let new_peaks = reachable_peaks(map, next_position);
-                peaks.extend(&new_peaks);
-            }
-        }
-    }
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day10.pest"]
+struct Day10Parser;
 
-    peaks
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Map, InputError> {
+    Map::try_from_strings(map_rows(file_rule, Rule::row)?.into_iter())
 }
 
-fn sum_of_trailhead_scores(map:&Map) -> usize {
+fn read_input(input:&str) -> Result<Map, InputError> {
+    build_file(parse_rule::<Day10Parser, _>(Rule::file, input)?)
+}
 
-    let is_trailhead = |position:&Position| {
-        map.at(*position).value == 0
-    };
+// list all peaks that can be reached from start_position
+fn reachable_peaks(map:&Map, start_position:Position) -> HashSet<Position> {
+    let uphill = |from:Position, to:Position| map.at(to).value == map.at(from).value + 1;
+    let field = distance_field(map, [start_position], uphill);
+    map.area.all_positions()
+        .filter(|&position| field.distances[position].is_some() && map.at(position).value == 9)
+        .collect()
+}
+
+fn sum_of_trailhead_scores(map:&Map) -> usize {
 
     let score_of_trailhead = |trailhead:Position| {
         reachable_peaks(&map, trailhead).len()
     };
 
-    map.area.all_positions().filter(is_trailhead).map(score_of_trailhead).sum()
+    map.find_all(TRAILHEAD).map(score_of_trailhead).sum()
 }
 
 // count all ways to the peak from start_position
@@ -69,21 +74,18 @@ fn count_ways_to_peak(map:&Map, start_position:Position) -> u32 {
 
 fn sum_of_trailhead_rating(map:&Map) -> u32 {
 
-    let is_trailhead = |position:&Position| {
-        map.at(*position).value == 0
-    };
-
     let rating_of_trailhead = |trailhead:Position| {
         count_ways_to_peak(&map, trailhead)
     };
 
-    map.area.all_positions().filter(is_trailhead).map(rating_of_trailhead).sum()
+    map.find_all(TRAILHEAD).map(rating_of_trailhead).sum()
 }
 
 
 
 #[test]
 fn test_trail() {
+    use crate::maps::Point;
     let input1 =
 "0123
 1234
@@ -92,8 +94,8 @@ fn test_trail() {
     let map1 = Map::from_strings(input1.split('\n'));
     assert_eq!(map1.width(), 4);
     assert_eq!(map1.height(), 4);
-    assert_eq!(reachable_peaks(&map1, (0,3)), HashSet::from([(0,3)]));
-    assert_eq!(reachable_peaks(&map1, (0,3)), HashSet::from([(0,3)]));
+    assert_eq!(reachable_peaks(&map1, Point(0,3)), HashSet::from([Point(0,3)]));
+    assert_eq!(reachable_peaks(&map1, Point(0,3)), HashSet::from([Point(0,3)]));
     assert_eq!(sum_of_trailhead_scores(&map1), 1);
 
     let input2 =
@@ -108,16 +110,16 @@ fn test_trail() {
     let map2 = Map::from_strings(input2.split('\n'));
     assert_eq!(map2.width(), 8);
     assert_eq!(map2.height(), 8);
-    assert_eq!(reachable_peaks(&map2, (0,0)), HashSet::from([(1,0)]));
-    assert_eq!(reachable_peaks(&map2, (2,0)).len(), 5);
-    assert_eq!(reachable_peaks(&map2, (4,0)).len(), 6);
-    assert_eq!(reachable_peaks(&map2, (4,2)).len(), 5);
-    assert_eq!(reachable_peaks(&map2, (6,4)).len(), 3);
-    assert_eq!(reachable_peaks(&map2, (2,5)).len(), 1);
-    assert_eq!(reachable_peaks(&map2, (5,5)).len(), 3);
-    assert_eq!(reachable_peaks(&map2, (0,6)).len(), 5);
-    assert_eq!(reachable_peaks(&map2, (6,6)).len(), 3);
-    assert_eq!(reachable_peaks(&map2, (1,7)).len(), 5);
+    assert_eq!(reachable_peaks(&map2, Point(0,0)), HashSet::from([Point(1,0)]));
+    assert_eq!(reachable_peaks(&map2, Point(2,0)).len(), 5);
+    assert_eq!(reachable_peaks(&map2, Point(4,0)).len(), 6);
+    assert_eq!(reachable_peaks(&map2, Point(4,2)).len(), 5);
+    assert_eq!(reachable_peaks(&map2, Point(6,4)).len(), 3);
+    assert_eq!(reachable_peaks(&map2, Point(2,5)).len(), 1);
+    assert_eq!(reachable_peaks(&map2, Point(5,5)).len(), 3);
+    assert_eq!(reachable_peaks(&map2, Point(0,6)).len(), 5);
+    assert_eq!(reachable_peaks(&map2, Point(6,6)).len(), 3);
+    assert_eq!(reachable_peaks(&map2, Point(1,7)).len(), 5);
     assert_eq!(sum_of_trailhead_scores(&map2), 36);
     assert_eq!(sum_of_trailhead_rating(&map2), 81);
 
@@ -130,6 +132,8 @@ fn test_trail() {
 567892";
     let map3 = Map::from_strings(input3.split('\n'));
     assert_eq!(sum_of_trailhead_rating(&map3), 227);
+    assert_eq!(read_input(input3).unwrap().width(), 6);
+    assert_eq!(read_input("0123\n12.4").err().unwrap().to_string(), "line 2, column 3: expected row");
 
 }
 
@@ -137,17 +141,12 @@ fn test_trail() {
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 
 pub fn puzzle() {
-    let file = File::open("input/day10.txt").expect("Could not open input/day10.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
-    let map = Map::from_strings(lines.iter().map( |line| line.as_str() ));
+    let input = read_file_to_string("input/day10.txt");
+    let map = expect_input(read_input(&input), "input/day10.txt", &input);
 
     println!("Day 10, Part 1: Sum of trailhead scores is {}", sum_of_trailhead_scores(&map));
     println!("Day 10, Part 2: Sum of trailhead rating is {}", sum_of_trailhead_rating(&map));
@@ -18,6 +18,32 @@ fn split_even_numbered_stone(stone:Stone) -> Option<(Stone, Stone)> {
     }
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{parse_rule, value};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day11.pest"]
+struct Day11Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Vec<Stone>, InputError> {
+    file_rule.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::stone)
+        .map(|pair| value::<Stone>(pair.as_str()).map_err(|err| err.at_pair(&pair)))
+        .collect()
+}
+
+fn read_input(input:&str) -> Result<Vec<Stone>, InputError> {
+    build_file(parse_rule::<Day11Parser, _>(Rule::file, input)?)
+}
+
+#[test]
+fn test_read_input() {
+    assert_eq!(read_input("125 17\n"), Ok(vec![125, 17]));
+    assert_eq!(read_input("125 17 99999999999999999999").unwrap_err().to_string(), "line 1, column 8: '99999999999999999999' is no u64");
+}
+
 #[test]
 fn test_split() {
     assert_eq!(split_even_numbered_stone(1), None);
@@ -138,9 +164,11 @@ fn test_change_stones() {
 /// Puzzle
 //////////////////////////////////////////
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day11.txt");
-    let input = lines[0].split(' ').map(|s| s.parse::<Stone>().unwrap()).collect::<Vec<Stone>>();
+    let text = read_file_to_string("input/day11.txt");
+    let input = expect_input(read_input(&text), "input/day11.txt", &text);
     let mut cache=Cache::new();
     println!("Day 11, Part 1: Number of stones after blinking 25 times is {}", input.iter().map(|&initial_stone| cache.number_of_stones_after_blinking_n_times(initial_stone, 25)).sum::<usize>());
     println!("Day 11, Part 2: Number of stones after blinking 75 times is {}", input.iter().map(|&initial_stone| cache.number_of_stones_after_blinking_n_times(initial_stone, 75)).sum::<usize>());
@@ -1,14 +1,30 @@
-use std::collections::HashSet;
+use crate::maps::{components, outlines, Connectivity, Svg};
+use crate::image::{palette, Rgb};
 
-use crate::maps::Position;
-use crate::maps::Direction;
-use Direction::*;
-
-const VERBOSE:bool = false;
+// the regions are written as an image and as a drawing to frames/
+const WRITE_IMAGE:bool = false;
+const WRITE_SVG:bool = false;
 
 type Plant = char;
 type PlantMap = crate::maps::PixelMap<Plant>;
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day12.pest"]
+struct Day12Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<PlantMap, InputError> {
+    PlantMap::try_from_strings(map_rows(file_rule, Rule::row)?.into_iter())
+}
+
+fn read_input(input:&str) -> Result<PlantMap, InputError> {
+    build_file(parse_rule::<Day12Parser, _>(Rule::file, input)?)
+}
+
 #[derive(Debug, PartialEq)]
 struct Region {
     plant:Plant,
@@ -17,93 +33,33 @@ struct Region {
     corners:u32 // = number of sides
 }
 
-fn extract_region(map:&PlantMap, start_position:Position, positions_done:&mut HashSet<Position>) -> Region {
-    let mut position_backlog = vec![start_position];
-    let plant = map.at(start_position);
-    if VERBOSE { println!("extract region {}", plant);}
-    let mut area = 0;
-    let mut perimeter = 0;
-    let mut corners = 0;
-    while position_backlog.len() > 0 {
-        let current_pos = position_backlog.pop().unwrap();
-
-        let current_plant = map.at(current_pos);
-        assert_eq!(current_plant, plant);
-        if VERBOSE { print!("  ({},{}): {} -> ", current_pos.0, current_pos.1, current_plant);}
-
-        if positions_done.contains(&current_pos) {
-            if VERBOSE { println!("  ({},{}): been there. done that.", current_pos.0, current_pos.1);}
-            continue;
-        }
-        positions_done.insert(current_pos);
-        area += 1;
-        let mut neigbour_equal : Vec<bool> = Vec::new();
-        for direction in Direction::four_directions() {
-            match map.area.step(current_pos, direction) {
-                Some(next_pos) => {
-                    let next_plant = map.at(next_pos);
-                    if next_plant == current_plant {
-                        if !positions_done.contains(&next_pos) {
-                            position_backlog.push(next_pos);
-                            if VERBOSE { print!("Put ({},{}) to backlog ", next_pos.0, next_pos.1);}
-                        }
-                        neigbour_equal.push(true);
-                    } else {
-                        perimeter += 1;
-                        if VERBOSE { println!("{:?} = perimeter", direction);}
-                        neigbour_equal.push(false);
-                    }
-                },
-                None => {
-                    perimeter += 1;
-                    if VERBOSE { print!("{:?} = border ", direction);}
-                    neigbour_equal.push(false);
-                }
-            }
-        }
-        match neigbour_equal.as_slice() {
-            [true,  true,  true,  true ] => {}, // inner point
-            [true,  true,  true,  false] |
-            [true,  true,  false, true ] |
-            [true,  false, true,  true ] |
-            [false, true,  true,  true ] => {}, // side
-            [true,  false, true,  false] |
-            [false, true,  false, true ] => {}, // tunnel
-            [false, true,  true,  false] |
-            [false, false, true,  true ] |
-            [true,  false, false, true ] |
-            [true,  true,  false, false] => { corners += 1 }, // corner
-            [false, false, false, true ] |
-            [false, false, true,  false] |
-            [false, true,  false, false] |
-            [true,  false, false, false] => { corners += 2 }, // half-island
-            [false, false, false, false] => { corners += 4 },  // minibox
-            _ => unreachable!()
-        }
-        // now we need to check for inbound edges
-        // AB
-        // AA
-        if neigbour_equal[0] && neigbour_equal[1] && { let diag = map.area.step(current_pos,DownRight); diag.is_some() && map.at(diag.unwrap()) != current_plant } { corners += 1 };
-        if neigbour_equal[1] && neigbour_equal[2] && { let diag = map.area.step(current_pos,DownLeft);  diag.is_some() && map.at(diag.unwrap()) != current_plant } { corners += 1 };
-        if neigbour_equal[2] && neigbour_equal[3] && { let diag = map.area.step(current_pos,UpLeft);    diag.is_some() && map.at(diag.unwrap()) != current_plant } { corners += 1 };
-        if neigbour_equal[3] && neigbour_equal[0] && { let diag = map.area.step(current_pos,UpRight);   diag.is_some() && map.at(diag.unwrap()) != current_plant } { corners += 1 };
-
-        if VERBOSE { println!("");}
-
-    }
-
-    Region { plant, area, perimeter, corners }
+fn extract_regions(map:&PlantMap) -> Vec<Region> {
+    let (_, components) = components(map, Connectivity::Four, |a, b| a == b);
+    components.iter().map(|component| Region {
+        plant:map.at(component.cells[0]),
+        area:component.area as u32,
+        perimeter:component.perimeter as u32,
+        corners:component.sides as u32
+    }).collect()
 }
 
-fn extract_regions(map:&PlantMap) -> Vec<Region> {
-    let mut regions = Vec::new();
-    let mut positions_done = HashSet::new();
+// every region in its own colour with its fences, labelled with the plant and the number of sides
+fn draw_regions(map:&PlantMap) -> Svg {
+    let (labels, components) = components(map, Connectivity::Four, |a, b| a == b);
+    let mut svg = Svg::new(&map.area, 16);
+    // all cells first, so no fence is covered by a neighbouring region
     for pos in map.area.all_positions() {
-        if !positions_done.contains(&pos) {
-            regions.push(extract_region(&map, pos, &mut positions_done));
+        let Rgb(r, g, b) = palette(labels[pos]);
+        svg = svg.cell(pos, &format!("rgb({},{},{})", r, g, b));
+    }
+    for (label, component) in components.iter().enumerate() {
+        for outline in outlines(&labels, label) {
+            svg = svg.polygon(&outline, "black");
         }
+        let first = component.cells[0];
+        svg = svg.point(first, &format!("{} {}", map.at(first), component.sides), "blue");
     }
-    regions
+    svg
 }
 
 fn sum_of_region_fencing_prices(regions:&Vec<Region>) -> u32 {
@@ -116,13 +72,16 @@ fn sum_of_region_fencing_prices_discounted(regions:&Vec<Region>) -> u32 {
 
 #[test]
 fn test_region() {
+    use crate::maps::Point;
     let input1 =
 "AAAA
 BBCD
 BBCC
 EEEC";
     let map1 = PlantMap::from_strings(input1.split('\n'));
-    assert_eq!(map1.at((2,1)), 'C');
+    assert_eq!(map1.at(Point(2,1)), 'C');
+    assert_eq!(read_input(input1).unwrap(), map1);
+    assert_eq!(read_input("AAAA\nBBCDE").err().unwrap().to_string(), "line 2, column 5: row has 5 characters instead of 4");
     let regions1 = extract_regions(&map1);
     assert_eq!(regions1, vec![
         Region{plant:'A', area: 4, perimeter: 10, corners: 4},
@@ -145,6 +104,13 @@ OOOOO";
     assert_eq!(regions2.len(), 5);
     assert_eq!(sum_of_region_fencing_prices(&regions2), 772);
     assert_eq!(sum_of_region_fencing_prices_discounted(&regions2), 436);
+    // the outer fence and one fence around every X
+    let svg = draw_regions(&map2).to_string();
+    assert_eq!(svg.matches("<polygon").count(), 1 + 4 + 4);
+    // the background and every cell
+    assert_eq!(svg.matches("<rect").count(), 1 + 25);
+    assert_eq!(svg.matches(&format!("fill=\"rgb({},{},{})\"", palette(0).0, palette(0).1, palette(0).2)).count(), 21);
+    assert!(svg.contains(">O 20</text>"));
 
     let input3 =
 "RRRRIICCFF
@@ -195,21 +161,29 @@ AAAAAA";
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use std::time::Instant;
 
+use crate::helper::{expect_input, read_file_to_string};
+use crate::image::write_frame;
 
-pub fn puzzle() {
-    let file = File::open("input/day12.txt").expect("Could not open input/day12.txt");
-    let reader = BufReader::new(file);
 
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
-    let map = PlantMap::from_strings(lines.iter().map( |line| line.as_str() ));
+pub fn puzzle() {
+    let input = read_file_to_string("input/day12.txt");
+    let map = expect_input(read_input(&input), "input/day12.txt", &input);
 
+    let start = Instant::now();
     let regions = extract_regions(&map);
+    let duration = start.elapsed();
 
-    println!("Day 12, Part 1: Sum of region-fencing-prices {}", sum_of_region_fencing_prices(&regions));
+    println!("Day 12, Part 1: Sum of region-fencing-prices {} (regions calculated in {} µs)", sum_of_region_fencing_prices(&regions), duration.as_micros());
     println!("Day 12, Part 2: Sum of region-fencing-prices (discounted) {}", sum_of_region_fencing_prices_discounted(&regions));
 
+    if WRITE_IMAGE {
+        let (labels, _) = components(&map, Connectivity::Four, |a, b| a == b);
+        write_frame(&labels, "day12_regions", 0, 4).expect("Could not write image");
+    }
+    if WRITE_SVG {
+        draw_regions(&map).save("day12_regions").expect("Could not write drawing");
+    }
+
 }
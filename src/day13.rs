@@ -1,22 +1,24 @@
 const VERBOSE:bool = false;
 
+use crate::maps::{Point, Vector};
+
 type Cost = u64;
-type Position = i64;
+type Coordinate = i64;
 
 const COST_OF_A:Cost = 3;
 const COST_OF_B:Cost = 1;
-const PART_B_OFFSET:Position = 10000000000000;
+const PART_B_OFFSET:Coordinate = 10000000000000;
 
 #[derive(Debug, PartialEq)]
 struct Machine {
     // button A moves (X,Y)
-    a:(Position, Position),
-    b:(Position, Position),
-    prize:(Position, Position)
+    a:Vector<Coordinate>,
+    b:Vector<Coordinate>,
+    prize:Point<Coordinate>
 }
 
 // solve equation m * a + n * b = prize
-fn solve_equation(a:(Position, Position), b:(Position, Position), prize:(Position, Position)) -> (/*m: */Position, /*n: */ Position) {
+fn solve_equation(a:Vector<Coordinate>, b:Vector<Coordinate>, prize:Point<Coordinate>) -> (/*m: */Coordinate, /*n: */ Coordinate) {
     // m * a.0 + n * b.0 = prize.0
     // m * a.1 + n * b.1 = prize.1
     // ---------------------------
@@ -51,8 +53,7 @@ impl Machine {
                 m * self.a.0 + n * self.b.0, m * self.a.1 + n * self.b.1
             )}
         if m >=0 && n >= 0 /* && m < 100 && n < 100 */ &&
-           m * self.a.0 + n * self.b.0 == self.prize.0 &&
-           m * self.a.1 + n * self.b.1 == self.prize.1 {
+           Point(0, 0) + self.a * m + self.b * n == self.prize {
                let cost:Cost = m as Cost *COST_OF_A + n as Cost *COST_OF_B;
                if VERBOSE { println!(" Cost = {}", cost); }
                Some(cost)
@@ -66,7 +67,7 @@ impl Machine {
         Machine {
             a:self.a,
             b:self.b,
-            prize:(self.prize.0 + PART_B_OFFSET, self.prize.1 + PART_B_OFFSET)
+            prize:self.prize + Vector(PART_B_OFFSET, PART_B_OFFSET)
         }
     }
 }
@@ -75,36 +76,44 @@ impl Machine {
 /// Parsing
 //////////////////////////////////////////
 
-use pest::Parser;
 use pest_derive::Parser;
 use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{parse_rule, value};
 
 #[derive(Parser)]
 #[grammar = "../grammar/day13.pest"]
 struct Day13Parser;
 
 
-fn build_machine(machine_rule:Pair<'_, Rule>) -> Machine {
-    let numbers:Vec<Position> = machine_rule.into_inner().map(|pair| pair.as_str().parse::<Position>().unwrap() ).collect();
-    Machine {
-        a:(numbers[0], numbers[1]),
-        b:(numbers[2], numbers[3]),
-        prize:(numbers[4],numbers[5])
-    }
+fn build_machine(machine_rule:Pair<'_, Rule>) -> Result<Machine, InputError> {
+    let numbers = machine_rule.into_inner()
+        .map(|pair| value::<Coordinate>(pair.as_str()).map_err(|err| err.at_pair(&pair)))
+        .collect::<Result<Vec<Coordinate>, InputError>>()?;
+    Ok(Machine {
+        a:Vector(numbers[0], numbers[1]),
+        b:Vector(numbers[2], numbers[3]),
+        prize:Point(numbers[4],numbers[5])
+    })
 }
 
-fn build_file(file_rule:Pair<'_, Rule>) -> Vec<Machine> {
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Vec<Machine>, InputError> {
     let mut machines = Vec::new();
     for pair in file_rule.into_inner() {
         match pair.as_rule() {
             Rule::machine => {
-                let machine = build_machine(pair);
+                let machine = build_machine(pair)?;
                 machines.push(machine);
             }
-            _ => { println!("Unexpected {}", pair); }
+            Rule::EOI => {},
+            _ => unreachable!()
         }
     }
-    machines
+    Ok(machines)
+}
+
+fn read_input(input:&str) -> Result<Vec<Machine>, InputError> {
+    build_file(parse_rule::<Day13Parser, _>(Rule::file, input)?)
 }
 
 
@@ -130,6 +139,8 @@ Prize: X=18641, Y=10279
 
 #[test]
 fn test_parse() {
+    use pest::Parser;
+
     let parse1 = Day13Parser::parse(Rule::number, "42").unwrap().peek().unwrap();
     assert_eq!(parse1.as_rule(), Rule::number);
     assert_eq!(parse1.as_str(), "42");
@@ -141,23 +152,26 @@ Prize: X=8400, Y=5400
 ";
 
     assert!(Day13Parser::parse(Rule::machine, input1).is_ok());
-    let machine1 = build_machine(Day13Parser::parse(Rule::machine, input1).unwrap().peek().unwrap());
-    assert_eq!(machine1, Machine{a:(94,34),b:(22,67),prize:(8400,5400)});
+    let machine1 = build_machine(Day13Parser::parse(Rule::machine, input1).unwrap().peek().unwrap()).unwrap();
+    assert_eq!(machine1, Machine{a:Vector(94,34),b:Vector(22,67),prize:Point(8400,5400)});
 
     assert!(Day13Parser::parse(Rule::file, example1()).is_ok());
-    let machines = build_file(Day13Parser::parse(Rule::file, example1()).unwrap().peek().unwrap());
+    let machines = read_input(example1()).unwrap();
     assert_eq!(machines.len(), 4);
 
-    assert_eq!(solve_equation((94, 34), (22, 67), (8400, 5400)), (80, 40));
+    let error = read_input("Button A: X+94, Y+34\nButton B: X+22, Y-67\nPrize: X=8400, Y=5400\n").err().unwrap();
+    assert_eq!(error.to_string(), "line 2, column 15: expected ', Y+' or number");
+
+    assert_eq!(solve_equation(Vector(94, 34), Vector(22, 67), Point(8400, 5400)), (80, 40));
 }
 
 #[test]
 fn test_machine() {
-    let machines = build_file(Day13Parser::parse(Rule::file, example1()).unwrap().peek().unwrap());
+    let machines = read_input(example1()).unwrap();
 
-    assert_eq!(solve_equation((94, 34), (22, 67), (8400, 5400)), (80, 40));
+    assert_eq!(solve_equation(Vector(94, 34), Vector(22, 67), Point(8400, 5400)), (80, 40));
     assert_eq!(machines[0].get_cost_to_win(), Some(280));
-    assert_eq!(solve_equation((26, 66), (67, 21), (12748, 12176)), (141, 135));
+    assert_eq!(solve_equation(Vector(26, 66), Vector(67, 21), Point(12748, 12176)), (141, 135));
     assert_eq!(machines[1].get_cost_to_win(), None);
     assert_eq!(machines[2].get_cost_to_win(), Some(200));
     assert_eq!(machines[3].get_cost_to_win(), None);
@@ -176,10 +190,11 @@ fn test_machine() {
 /// Puzzle
 //////////////////////////////////////////
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day13.txt");
-    let lines_concatenated = lines.join("\n");
-    let machines = build_file(Day13Parser::parse(Rule::file, &lines_concatenated ).unwrap().peek().unwrap());
+    let input = read_file_to_string("input/day13.txt");
+    let machines = expect_input(read_input(&input), "input/day13.txt", &input);
 
     let sum_of_cost1 = machines.iter().map(|machine| machine.get_cost_to_win().unwrap_or(0)).sum::<Cost>();
     println!("Day 13, Part 1: Sum of costs to win all prizes is {}", sum_of_cost1);
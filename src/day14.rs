@@ -1,25 +1,27 @@
-type Position = (usize,usize);
-type Velocity = (i32,i32);
+use crate::maps::{Area, Orientations, Point, Position, Rect, Template, Vector};
+
+type Velocity = Vector<i64>;
 type Width = usize;
 type Height = usize;
 
 const VERBOSE:bool = false;
+// the first 500 seconds are written as images to frames/
+const WRITE_FRAMES:bool = false;
+const WRITE_ANIMATION:bool = false;
 
+// robots leaving the bathroom teleport to the other side
 struct Bathroom {
-    width:Width,
-    height:Height
+    area:Area
 }
 
-type QuadrantCounter = (usize,usize,usize,usize);
-
 impl Bathroom {
-    fn get_quadrant_counter(&self, position:Position) -> QuadrantCounter {
-        if position.0 > self.width / 2 && position.1 < self.height / 2 { (1,0,0,0) } else
-        if position.0 < self.width / 2 && position.1 < self.height / 2 { (0,1,0,0) } else
-        if position.0 > self.width / 2 && position.1 > self.height / 2 { (0,0,1,0) } else
-        if position.0 < self.width / 2 && position.1 > self.height / 2 { (0,0,0,1) } else {
-            (0,0,0,0)
-        }
+    fn new(width:Width, height:Height) -> Self {
+        Bathroom { area:Area::toroidal(width, height) }
+    }
+
+    // robots on the middle row or column are in no quadrant
+    fn quadrants(&self) -> [Rect;4] {
+        Rect::from(&self.area).quadrants(true)
     }
 }
 
@@ -29,52 +31,52 @@ struct Robot {
     velocity:Velocity
 }
 
-use regex::Regex;
+use crate::helper::InputError;
+use crate::helper::parse::{integers, parse_lines, ParseError};
 
 impl Robot {
 
     // "p=0,4 v=3,-3"
-    fn from_string(line:&str) -> Robot {
-
-        let r = Regex::new(r"p=(\d+),(\d+) v=(-?\d+),(-?\d+)").unwrap();
-        let caps = r.captures(line).unwrap();
-        let px = caps.get(1).unwrap().as_str().parse::<usize>().unwrap();
-        let py = caps.get(2).unwrap().as_str().parse::<usize>().unwrap();
-        let vx = caps.get(3).unwrap().as_str().parse::<i32>().unwrap();
-        let vy = caps.get(4).unwrap().as_str().parse::<i32>().unwrap();
+    fn from_string(line:&str) -> Result<Robot, ParseError> {
+
+        let numbers = integers::<i64>(line)?;
+        let [px, py, vx, vy] = numbers[..] else {
+            return Err(ParseError::new(1, format!("expected 4 numbers like 'p=0,4 v=3,-3', found {}", numbers.len())));
+        };
+        if px < 0 || py < 0 {
+            return Err(ParseError::new(1, format!("position ({},{}) is outside of the bathroom", px, py)));
+        }
 
-        Robot { position:(px,py),velocity:(vx,vy) }
+        Ok(Robot { position:Point(px as usize,py as usize),velocity:Vector(vx,vy) })
 
     }
 
     // move the robot {counter} times through {bathroom}
     fn move_robot(&self, bathroom:&Bathroom, counter:usize) -> Position {
-        let vx = self.velocity.0;
-        let vy = self.velocity.1;
-        let dx = if vx > 0 { vx as usize } else { (bathroom.width  as i32 + vx) as usize };
-        let dy = if vy > 0 { vy as usize } else { (bathroom.height as i32 + vy) as usize };
-        ((self.position.0 + counter * dx ) % bathroom.width,
-         (self.position.1 + counter * dy ) % bathroom.height)
+        bathroom.area.move_by(self.position, self.velocity, counter as i64).unwrap()
     }
 
 }
 
+fn read_robots(input:&str) -> Result<Vec<Robot>, InputError> {
+    parse_lines(input, Robot::from_string)
+}
+
 fn get_safety_factor<Iter:Iterator<Item=Position>>(bathroom:&Bathroom, positions:Iter) -> usize {
-    let mut counters:QuadrantCounter = (0,0,0,0);
+    let quadrants = bathroom.quadrants();
+    let mut counters = [0;4];
     for pos in positions {
-        let counter = bathroom.get_quadrant_counter(pos);
-        counters.0 += counter.0;
-        counters.1 += counter.1;
-        counters.2 += counter.2;
-        counters.3 += counter.3;
+        if let Some(quadrant) = quadrants.iter().position(|rect| rect.contains(pos)) {
+            counters[quadrant] += 1;
+        }
     }
-    counters.0 * counters.1 * counters.2 * counters.3
+    counters.iter().product()
 }
 
 type Image = crate::maps::PixelMap<char>;
 
 fn positions_to_image<Iter:Iterator<Item=Position>>(bathroom:&Bathroom, positions:Iter) -> Image {
-    let mut image  = Image::new(bathroom.width, bathroom.height, '.');
+    let mut image  = Image::new(bathroom.area.width, bathroom.area.height, '.');
 
     for pos in positions {
         image.set_at(pos,'*');
@@ -112,14 +114,14 @@ fn print_positions<Iter:Iterator<Item=Position>>(bathroom:&Bathroom, positions:I
 
 #[test]
 fn test_move() {
-    let bathroom = Bathroom{width:11, height:7};
-    let robot = Robot::from_string("p=2,4 v=2,-3");
-    assert_eq!(robot.move_robot(&bathroom, 0), (2,4) );
-    assert_eq!(robot.move_robot(&bathroom, 1), (4,1) );
-    assert_eq!(robot.move_robot(&bathroom, 2), (6,5) );
-    assert_eq!(robot.move_robot(&bathroom, 3), (8,2) );
-    assert_eq!(robot.move_robot(&bathroom, 4), (10,6) );
-    assert_eq!(robot.move_robot(&bathroom, 5), (1,3) );
+    let bathroom = Bathroom::new(11, 7);
+    let robot = Robot::from_string("p=2,4 v=2,-3").unwrap();
+    assert_eq!(robot.move_robot(&bathroom, 0), Point(2,4) );
+    assert_eq!(robot.move_robot(&bathroom, 1), Point(4,1) );
+    assert_eq!(robot.move_robot(&bathroom, 2), Point(6,5) );
+    assert_eq!(robot.move_robot(&bathroom, 3), Point(8,2) );
+    assert_eq!(robot.move_robot(&bathroom, 4), Point(10,6) );
+    assert_eq!(robot.move_robot(&bathroom, 5), Point(1,3) );
 }
 #[test]
 fn test_example() {
@@ -136,12 +138,13 @@ p=9,3 v=2,3
 p=7,3 v=-1,2
 p=2,4 v=2,-3
 p=9,5 v=-3,-3";
-    let robots = input1.split('\n').map(|line| Robot::from_string(line)).collect::<Vec<Robot>>();
+    let robots = read_robots(input1).unwrap();
     assert_eq!(robots.len(), 12);
-    assert_eq!(robots[0], Robot{position:(0,4),velocity:(3,-3)});
-    assert_eq!(robots[1], Robot{position:(6,3),velocity:(-1,-3)});
+    assert_eq!(robots[0], Robot{position:Point(0,4),velocity:Vector(3,-3)});
+    assert_eq!(robots[1], Robot{position:Point(6,3),velocity:Vector(-1,-3)});
+    assert_eq!(read_robots("p=0,4 v=3,-3\np=-6,3 v=-1,-3").unwrap_err().to_string(), "line 2, column 1: position (-6,3) is outside of the bathroom");
 
-    let bathroom = Bathroom{width:11, height:7};
+    let bathroom = Bathroom::new(11, 7);
     let positions = robots.iter().map(|robot| robot.move_robot(&bathroom, 100));
     assert_eq!(get_safety_factor(&bathroom, positions), 12);
 }
@@ -151,33 +154,38 @@ p=9,5 v=-3,-3";
 //////////////////////////////////////////
 
 fn find_substring(image:&Image, substring:&str) -> bool {
-    for y in 0..image.area.height {
-        for startx in 0..image.area.width - substring.len() {
-            let mut is_match = true;
-            let mut subs = substring.chars().into_iter();
-            for x in 0..substring.len() {
-                if image.pixels[y][startx+x] != subs.next().unwrap() {
-                    is_match = false;
-                }
-            }
-            if is_match { return true; }
-        }
+    let template = Template::template_from_strings([substring].into_iter(), '?');
+    !image.find_pattern(&template, Orientations::Fixed).is_empty()
+}
+
+use crate::helper::{expect_input, read_file_to_string};
+use crate::image::{create_animation, write_frame};
+
+use crate::trace::{Event, Recorder};
+
+// one event per second with the positions of all robots
+fn trace_robots(bathroom:&Bathroom, robots:&[Robot], seconds:usize, recorder:&mut Recorder) {
+    for second in 0..=seconds {
+        recorder.record(Event::new(14, second).robots(robots.iter().map(|robot| robot.move_robot(bathroom, second))));
     }
-    false
 }
 
-pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day14.txt");
-    let robots = lines.iter().map(|line| Robot::from_string(line)).collect::<Vec<Robot>>();
+pub fn puzzle(recorder:&mut Recorder) {
+    let input = read_file_to_string("input/day14.txt");
+    let robots = expect_input(read_robots(&input), "input/day14.txt", &input);
 
-    let bathroom = Bathroom{width:101, height:103};
+    let bathroom = Bathroom::new(101, 103);
+    if recorder.enabled() { trace_robots(&bathroom, &robots, 100, recorder); }
     let positions = robots.iter().map(|robot| robot.move_robot(&bathroom, 100));
     let safety_factor = get_safety_factor(&bathroom, positions);
     println!("Day 14, Part 1: Safety factor after moving {} robots for 100 seconds is {}", robots.len(), safety_factor);
 
+    let mut animation = if WRITE_ANIMATION { Some(create_animation("day14", 10, 2).expect("Could not create animation")) } else { None };
     for moves in 0..10000 {
         let positions = robots.iter().map(|robot| robot.move_robot(&bathroom, moves));
         let image = positions_to_image(&bathroom, positions);
+        if WRITE_FRAMES && moves < 500 { write_frame(&image, "day14", moves, 4).expect("Could not write frame"); }
+        if let Some(animation) = &mut animation { animation.add_frame(&image).expect("Could not write animation"); }
         // assumption: a christmas tree has '**********' in it
         if find_substring(&image, "***********") {
             if VERBOSE {
@@ -187,4 +195,5 @@ pub fn puzzle() {
             println!("Day 14, Part 2: Christmas tree could be possible visible after {} seconds. I DID NOT LIKE THIS PUZZLE 😒", moves);
         }
     }
+    if let Some(animation) = animation { animation.finish().expect("Could not write animation"); }
 }
@@ -1,13 +1,15 @@
-use crate::maps::Position;
+use crate::maps::{Point, Position};
 use crate::maps::Direction;
 use crate::maps::FromChar;
 use crate::maps::PixelMap;
 
-#[cfg(test)]
-use crate::helper::split_input_sections;
+use crate::helper::{InputError, Sections};
 use crate::maps::Direction::*;
 
 const VERBOSE:bool = false;
+// the warehouse after the first 500 moves is written as images to frames/
+const WRITE_FRAMES:bool = false;
+const WRITE_ANIMATION:bool = false;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum MapElement {
@@ -16,23 +18,33 @@ enum MapElement {
     Box,
     BoxLeft,
     BoxRight,
-    Robot,
-    Unknown
+    Robot
 }
 
 use MapElement::*;
 
 
-impl crate::maps::FromChar for MapElement {
-    fn from_char(c:char) -> Self {
+impl crate::maps::TryFromChar for MapElement {
+    fn try_from_char(c:char) -> Option<Self> {
         match c {
-            '.' => Space,
-            '#' => Wall,
-            'O' => Box,
-            '[' => BoxLeft,
-            ']' => BoxRight,
-            '@' => Robot,
-            _ => panic!("Unexpected character {} for TestEnum", c)
+            '.' => Some(Space),
+            '#' => Some(Wall),
+            'O' => Some(Box),
+            '[' => Some(BoxLeft),
+            ']' => Some(BoxRight),
+            '@' => Some(Robot),
+            _ => None
+        }
+    }
+}
+
+impl crate::image::ToColor for MapElement {
+    fn to_color(self) -> Rgb {
+        match self {
+            Space => Rgb::BLACK,
+            Wall => Rgb::GRAY,
+            Box | BoxLeft | BoxRight => Rgb(245, 130, 48),
+            Robot => Rgb(60, 180, 75)
         }
     }
 }
@@ -45,8 +57,7 @@ impl crate::maps::ToChar for MapElement {
             Box => 'O',
             BoxLeft => '[',
             BoxRight => ']',
-            Robot => '@',
-            Unknown => '?'
+            Robot => '@'
         }
     }
 }
@@ -54,24 +65,31 @@ impl crate::maps::ToChar for MapElement {
 type Map = PixelMap<MapElement>;
 
 struct Puzzle {
-    map:Map,
+    map:Map, // without the robot
+    start:Position, // of the robot
     moves:Vec<Direction>
 }
 
 fn other_side_of_box(myself:MapElement, pos:Position) -> Position {
     match myself {
-        BoxLeft  => (pos.0 + 1, pos.1),
-        BoxRight => (pos.0 - 1, pos.1),
+        BoxLeft  => Point(pos.0 + 1, pos.1),
+        BoxRight => Point(pos.0 - 1, pos.1),
         _ => unreachable!()
     }
 }
 
 fn can_move_to(map:&Map, target_pos:Position, direction:Direction) -> bool {
     let target = map.at(target_pos);
-    match target {
-        Wall => { return false },
-        Space => { return true },
-        Box | BoxLeft | BoxRight => { return can_move_box(map, target_pos, direction) },
+    match (target, direction) {
+        (Wall, _) => { return false },
+        (Space, _) => { return true },
+        // a straight line of boxes can be pushed if there is space behind it
+        (Box, _) |
+        (BoxLeft | BoxRight, Left | Right) => {
+            let behind = map.first_in_ray(target_pos, direction, |element| element == Wall || element == Space);
+            behind.is_some_and(|pos| map.at(pos) == Space)
+        },
+        (BoxLeft | BoxRight, _) => { return can_move_box(map, target_pos, direction) },
         other => { panic!("Unexpected {:?} at {:?}", other, target_pos)}
     }
 }
@@ -152,43 +170,78 @@ fn move_box(map:&mut Map, pos:Position, direction:Direction) -> u32 {
 
 }
 
-fn read_input<'a>(map_lines:impl Iterator<Item=&'a str>, directions_lines:&str) -> Puzzle {
-    let map = PixelMap::from_strings(map_lines);
-    let moves = directions_lines.chars().map(|c| Direction::from_char(c)).collect();
-    Puzzle { map, moves }
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day15.pest"]
+struct Day15Parser;
+
+// the robot is replaced with Space
+fn build_map(map_rule:Pair<'_, Rule>) -> Result<(Map, Position), InputError> {
+    let mut map = PixelMap::try_from_strings(map_rows(map_rule, Rule::row)?.into_iter())?;
+    let [start] = map.extract_markers([Robot], Space)?;
+    Ok((map, start))
+}
+
+fn build_moves(moves_rule:Pair<'_, Rule>) -> Vec<Direction> {
+    moves_rule.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::move_line)
+        .flat_map(|pair| pair.as_str().chars().map(Direction::from_char).collect::<Vec<_>>())
+        .collect()
+}
+
+fn read_puzzle<'a>(lines:impl Iterator<Item=&'a str>) -> Result<Puzzle, InputError> {
+    let sections = Sections::read(lines, &["map", "moves"], &[])?;
+    let (map, start) = sections.get("map").parse(|text| build_map(parse_rule::<Day15Parser, _>(Rule::map, text)?))?;
+    let moves = sections.get("moves").parse(|text| Ok(build_moves(parse_rule::<Day15Parser, _>(Rule::moves, text)?)))?;
+    Ok(Puzzle { map, start, moves })
 }
 
-// extract robot start position and replace with Space
-fn extract_start_pos(map:&mut Map) -> Position {
-    if let Some(pos) = map.find_first(Robot) {
-        map.set_at(pos, Space);
-        return pos;
-    } else {
-        panic!("Could not find start position");
+// everything is twice as wide, the robot stays on the left half
+fn widen(element:MapElement) -> [MapElement;2] {
+    match element {
+        Wall  => [Wall, Wall],
+        Box   => [BoxLeft, BoxRight],
+        Space => [Space, Space],
+        other => panic!("Unexpected {:?}", other)
     }
 }
 
 fn convert_to_part2(puzzle:&Puzzle) -> Puzzle {
-    let     map = &puzzle.map;
-    let mut map2 = PixelMap::new(map.width() * 2, map.height(),Unknown);
-    for pos in map.area.all_positions() {
-        let (element1,element2) = match map.at(pos) {
-            Wall  => (Wall, Wall),
-            Box   => (BoxLeft, BoxRight),
-            Space => (Space, Space),
-            Robot => (Robot, Space),
-            other     => { panic!("Unexpected {:?} at {:?}", other, pos) }
-        };
-        map2.set_at((pos.0 * 2    ,pos.1), element1);
-        map2.set_at((pos.0 * 2 + 1,pos.1), element2);
+    Puzzle {
+        map:puzzle.map.scale(2, 1, |element, offset| widen(element)[offset.0]),
+        start:Point(puzzle.start.0 * 2, puzzle.start.1),
+        moves:puzzle.moves.clone()
     }
-    Puzzle { map:map2, moves:puzzle.moves.clone() }
 }
 
-fn execute_moves(puzzle:&Puzzle) -> Map {
+use crate::maps::ToChar;
+use crate::trace::{Event, Recorder};
+use crate::image::{create_animation, write_frame, Rgb};
+
+// cells which are different in {after} with their new content
+fn changed_cells(before:&Map, after:&Map) -> Vec<(Position, char)> {
+    before.diff(after).iter()
+        .map(|change| (change.position, change.new.to_char()))
+        .collect()
+}
+
+// the map with the robot for traces and frames
+fn with_robot(map:&Map, robot:Position) -> Map {
+    let mut frame = map.clone();
+    frame.set_at(robot, Robot);
+    frame
+}
+
+fn execute_moves(puzzle:&Puzzle, recorder:&mut Recorder) -> Map {
     let mut map = puzzle.map.clone();
-    let mut current_pos = extract_start_pos(&mut map);
-    for &direction in &puzzle.moves {
+    let mut current_pos = puzzle.start;
+    if recorder.enabled() { recorder.record(Event::new(15, 0).position(current_pos).map(&with_robot(&map, current_pos))); }
+    let mut animation = if WRITE_ANIMATION { Some(create_animation(&format!("day15_{}x{}", map.width(), map.height()), 2, 4).expect("Could not create animation")) } else { None };
+    for (step, &direction) in puzzle.moves.iter().enumerate() {
+        let before = if recorder.enabled() { Some(with_robot(&map, current_pos)) } else { None };
         let next_pos = map.area.step(current_pos, direction).unwrap();
         match map.at(next_pos) {
             Space => {
@@ -210,7 +263,16 @@ fn execute_moves(puzzle:&Puzzle) -> Map {
             _ => unreachable!()
         }
         if VERBOSE {map.println();}
+        if (WRITE_FRAMES && step < 500) || animation.is_some() {
+            let frame = with_robot(&map, current_pos);
+            if WRITE_FRAMES && step < 500 { write_frame(&frame, &format!("day15_{}x{}", map.width(), map.height()), step, 4).expect("Could not write frame"); }
+            if let Some(animation) = &mut animation { animation.add_frame(&frame).expect("Could not write animation"); }
+        }
+        if let Some(before) = before {
+            recorder.record(Event::new(15, step + 1).position(current_pos).direction(direction).changed_cells(&changed_cells(&before, &with_robot(&map, current_pos))));
+        }
     }
+    if let Some(animation) = animation { animation.finish().expect("Could not write animation"); }
     map
 }
 
@@ -242,17 +304,32 @@ fn input1() -> &'static str {
 #[test]
 fn test_puzzle1()
 {
-    let sections1 = split_input_sections::<2>(input1());
-    let puzzle1 = read_input(sections1[0].split('\n'), &sections1[1]);
-    assert_eq!(puzzle1.map.pixels[2], vec![Wall, Wall, Robot, Space, Box, Space, Space, Wall]);
+    use crate::maps::assert_maps_eq;
+    let puzzle1 = read_puzzle(input1().split('\n')).unwrap();
+    assert_eq!(puzzle1.map.row(2), vec![Wall, Wall, Space, Space, Box, Space, Space, Wall]);
+    assert_eq!(puzzle1.start, Point(2,2));
     assert_eq!(puzzle1.moves[0..7], [Left, Up, Up, Right, Right, Right, Down]);
-    let start_pos = extract_start_pos(&mut puzzle1.map.clone());
-    assert_eq!(start_pos, (2,2));
-    let final_map = execute_moves(&puzzle1);
+    let error = read_puzzle("####\n#@O#\n####\n\n<^^\n>>x".split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 6, column 3: expected '<' or '>' or '^' or 'v' or move_line");
+    let error = read_puzzle("####\n#@O#\n#.@#\n####\n\n<^^".split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 3, column 3: '@' is already at line 2, column 2");
+    assert_eq!(read_puzzle("####\n#.O#\n####\n\n<^^".split('\n')).err().unwrap().to_string(), "line 3: there is no '@' in the map");
+    let final_map = execute_moves(&puzzle1, &mut Recorder::disabled());
     assert_eq!(get_gps(&final_map), 2028);
+    // the robot is not part of the map while moving
+    assert_maps_eq(&final_map, &Map::from_strings(
+"########
+#....OO#
+##.....#
+#.....O#
+#.#O...#
+#...O..#
+#...O..#
+########".split('\n')));
 
     let puzzle1_2 = convert_to_part2(&puzzle1);
-    assert_eq!(puzzle1_2.map.pixels[2], vec![Wall, Wall, Wall, Wall, Robot, Space, Space, Space, BoxLeft, BoxRight, Space, Space, Space, Space, Wall, Wall]);
+    assert_eq!(puzzle1_2.map.row(2), vec![Wall, Wall, Wall, Wall, Space, Space, Space, Space, BoxLeft, BoxRight, Space, Space, Space, Space, Wall, Wall]);
+    assert_eq!(puzzle1_2.start, Point(4,2));
 }
 
 #[cfg(test)]
@@ -283,17 +360,39 @@ v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^"
 #[test]
 fn test_puzzle2()
 {
-    let sections = split_input_sections::<2>(input2());
-    let puzzle1 = read_input(sections[0].split('\n'), &sections[1].replace("\n",""));
-    let final_map1 = execute_moves(&puzzle1);
+    use crate::maps::assert_maps_eq;
+    let puzzle1 = read_puzzle(input2().split('\n')).unwrap();
+    let final_map1 = execute_moves(&puzzle1, &mut Recorder::disabled());
     if VERBOSE { final_map1.println(); }
     assert_eq!(get_gps(&final_map1), 10092);
+    assert_maps_eq(&final_map1, &Map::from_strings(
+"##########
+#.O.O.OOO#
+#........#
+#OO......#
+#OO......#
+#O#.....O#
+#O.....OO#
+#O.....OO#
+#OO....OO#
+##########".split('\n')));
 
     let puzzle2 = convert_to_part2(&puzzle1);
     if VERBOSE { puzzle2.map.println(); }
-    let final_map2 = execute_moves(&puzzle2);
+    let final_map2 = execute_moves(&puzzle2, &mut Recorder::disabled());
     if VERBOSE { final_map2.println(); }
     assert_eq!(get_gps(&final_map2), 9021);
+    assert_maps_eq(&final_map2, &Map::from_strings(
+"####################
+##[].......[].[][]##
+##[]...........[].##
+##[]........[][][]##
+##[]......[]....[]##
+##..##......[]....##
+##..[]............##
+##.........[].[][]##
+##......[][]..[]..##
+####################".split('\n')));
 
 }
 
@@ -313,34 +412,41 @@ fn input3() -> &'static str {
 #[test]
 fn test_puzzle3()
 {
-    let sections = split_input_sections::<2>(input3());
-    let puzzle1 = read_input(sections[0].split('\n'), &sections[1].replace("\n",""));
+    use crate::maps::assert_maps_eq;
+    let puzzle1 = read_puzzle(input3().split('\n')).unwrap();
     let puzzle2 = convert_to_part2(&puzzle1);
     if VERBOSE { puzzle2.map.println(); }
-    let final_map = execute_moves(&puzzle2);
+    let final_map = execute_moves(&puzzle2, &mut Recorder::disabled());
     if VERBOSE { final_map.println(); }
     assert_eq!(get_gps(&final_map), 105 + 207 + 306);
+    assert_maps_eq(&final_map, &Map::from_strings(
+"##############
+##...[].##..##
+##.....[]...##
+##....[]....##
+##..........##
+##..........##
+##############".split('\n')));
 }
 
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
-use crate::helper::split_lines_sections;
+use crate::helper::{expect_input, read_file_to_string};
 
-pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day15.txt");
+pub fn puzzle(recorder:&mut Recorder) {
+    let input = read_file_to_string("input/day15.txt");
 
-    let sections = split_lines_sections::<2>(lines);
-    let puzzle = read_input(sections[0].iter().map(|line| line.as_str()), &sections[1].join(""));
-    let final_map = execute_moves(&puzzle);
+    let puzzle = expect_input(read_puzzle(input.lines()), "input/day15.txt", &input);
+    let final_map = execute_moves(&puzzle, recorder);
     let gps = get_gps(&final_map);
 
     println!("Day 15, Part 1: GPS after moving is {}", gps);
 
     let puzzle2 = convert_to_part2(&puzzle);
 
-    let final_map2 = execute_moves(&puzzle2);
+    let final_map2 = execute_moves(&puzzle2, recorder);
     let gps2 = get_gps(&final_map2);
 
     println!("Day 15, Part 2: GPS after moving is {}", gps2);
@@ -2,9 +2,13 @@ use std::collections::HashSet;
 
 use crate::maps::Position;
 use crate::maps::Direction;
+use crate::maps::{junction_graph, Corridor, CorridorPaths, JunctionGraph};
 use crate::optimize::get_all_best_paths;
+use crate::render::{Color, Renderer, Style};
 use Direction::*;
 
+const VERBOSE:bool = false;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum MapElement {
     Space,
@@ -15,14 +19,25 @@ enum MapElement {
 
 use MapElement::*;
 
-impl crate::maps::FromChar for MapElement {
-    fn from_char(c:char) -> Self {
+impl crate::maps::TryFromChar for MapElement {
+    fn try_from_char(c:char) -> Option<Self> {
         match c {
-            '.' => Space,
-            '#' => Wall,
-            'S' => Start,
-            'E' => End,
-            _ => panic!("Unexpected character {} for TestEnum", c)
+            '.' => Some(Space),
+            '#' => Some(Wall),
+            'S' => Some(Start),
+            'E' => Some(End),
+            _ => None
+        }
+    }
+}
+
+impl crate::maps::ToChar for MapElement {
+    fn to_char(self) -> char {
+        match self {
+            Space => '.',
+            Wall => '#',
+            Start => 'S',
+            End => 'E'
         }
     }
 }
@@ -41,7 +56,6 @@ use Action::*;
 use crate::optimize::Cost;
 use crate::optimize::ActionTrait;
 use crate::optimize::Problem;
-use crate::optimize::get_cost_of_state;
 
 impl ActionTrait for Action {
 }
@@ -49,28 +63,47 @@ impl ActionTrait for Action {
 type State = (Position,Direction);
 
 struct Puzzle {
-    map:Map
+    map:Map, // Start and End are replaced with Space
+    start:Position,
+    end:Position
 }
 
 impl Puzzle {
-    fn read_input<'a>(map_lines:impl Iterator<Item=&'a str>) -> Puzzle {
-        Puzzle {
-            map:Map::from_strings(map_lines)
-        }
+    fn read_input<'a>(map_lines:impl Iterator<Item=&'a str>) -> Result<Puzzle, InputError> {
+        let mut map = Map::try_from_strings(map_lines)?;
+        let [start, end] = map.extract_markers([Start, End], Space)?;
+        Ok(Puzzle { map, start, end })
     }
 
     fn get_start_state(&self) -> State {
-        (self.map.find_first(Start).unwrap(), Right)
+        (self.start, Right)
     }
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day16.pest"]
+struct Day16Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Puzzle, InputError> {
+    Puzzle::read_input(map_rows(file_rule, Rule::row)?.into_iter())
+}
+
+fn read_puzzle(input:&str) -> Result<Puzzle, InputError> {
+    build_file(parse_rule::<Day16Parser, _>(Rule::file, input)?)
+}
+
 impl Problem for Puzzle {
 
     type Action = Action;
     type State = State;
 
     fn is_end_state(&self, state:&State) -> bool {
-        self.map.at(state.0) == End
+        state.0 == self.end
     }
 
     fn execute_action(&self, before:State, action:Action) -> Option<State> {
@@ -132,24 +165,79 @@ impl Problem for Puzzle {
 
 }
 
-fn count_tiles_which_are_part_of_any_best_path(puzzle:&Puzzle) -> usize {
-    let best_paths = get_all_best_paths(puzzle, puzzle.get_start_state());
+fn tiles_of_best_paths(puzzle:&Puzzle, best_paths:&[Vec<Action>]) -> HashSet<Position> {
     let mut relevant_positions : HashSet<Position> = HashSet::new();
     for path in best_paths {
         let mut state = puzzle.get_start_state();
         relevant_positions.insert(state.0);
-        for action in path {
+        for &action in path {
             state = puzzle.execute_action(state, action).unwrap();
             relevant_positions.insert(state.0);
         }
     }
+    relevant_positions
+}
+
+// the direction of every Walk in the path
+fn walking_directions(puzzle:&Puzzle, path:&[Action]) -> Vec<Direction> {
+    let mut state = puzzle.get_start_state();
+    let mut directions = Vec::new();
+    for &action in path {
+        if action == Walk { directions.push(state.1); }
+        state = puzzle.execute_action(state, action).unwrap();
+    }
+    directions
+}
+
+// all best tiles with the first best path on top
+fn print_best_paths(puzzle:&Puzzle, best_paths:&[Vec<Action>]) {
+    let mut renderer = Renderer::new(&puzzle.map)
+        .colors(|_, element| if element == Wall { Some(Style::foreground(Color::Blue)) } else { None })
+        .highlight(tiles_of_best_paths(puzzle, best_paths), Style::background(Color::Green))
+        .highlight([puzzle.start], Style::background(Color::Green).with_character('S'))
+        .highlight([puzzle.end], Style::background(Color::Green).with_character('E'));
+    if let Some(path) = best_paths.first() {
+        renderer = renderer.path(puzzle.get_start_state().0, walking_directions(puzzle, path), Style::foreground(Color::Yellow));
+    }
+    renderer.println();
+}
+
+// the same costs as the actions, the reindeer starts facing Right
+fn corridor_cost(previous:Option<&Corridor>, corridor:&Corridor) -> Cost {
+    let facing = previous.map_or(Right, |previous| previous.last);
+    let turns_at_node =
+        if corridor.first == facing { 0 }
+        else if corridor.first == facing.turn_right().turn_right() { 2 }
+        else { 1 };
+    corridor.length as Cost + 1000 * (corridor.turns + turns_at_node) as Cost
+}
 
-    relevant_positions.len()
+// searching the junctions instead of every tile and direction
+fn best_paths_through_corridors(puzzle:&Puzzle) -> (JunctionGraph, CorridorPaths, usize) {
+    let graph = junction_graph(&puzzle.map, |element| element != Wall, [puzzle.start, puzzle.end]);
+    let paths = graph.cheapest_paths(graph.node_at(puzzle.start).unwrap(), corridor_cost);
+    let end = graph.node_at(puzzle.end).unwrap();
+    (graph, paths, end)
+}
 
+fn lowest_score(puzzle:&Puzzle) -> Cost {
+    let (graph, paths, end) = best_paths_through_corridors(puzzle);
+    paths.cost_to(&graph, end).expect("There is no path from Start to End")
+}
+
+fn count_tiles_which_are_part_of_any_best_path(puzzle:&Puzzle) -> usize {
+    if VERBOSE { print_best_paths(puzzle, &get_all_best_paths(puzzle, puzzle.get_start_state())); }
+    let (graph, paths, end) = best_paths_through_corridors(puzzle);
+    let tiles:HashSet<Position> = paths.corridors_on_cheapest_paths(&graph, end).iter()
+        .flat_map(|&corridor| graph.positions(&[corridor]))
+        .collect();
+    tiles.len()
 }
 
 #[test]
 fn test_puzzle1() {
+    use crate::maps::Point;
+    use crate::optimize::get_cost_of_state;
     let input=
 "###############
 #.......#....E#
@@ -166,26 +254,33 @@ fn test_puzzle1() {
 #.###.#.#.#.#.#
 #S..#.....#...#
 ###############";
-    let puzzle = Puzzle::read_input(input.split('\n'));
+    let puzzle = Puzzle::read_input(input.split('\n')).unwrap();
     let start_pos = puzzle.get_start_state();
-    assert_eq!(start_pos, ((1, 13),Right));
-    assert_eq!(puzzle.execute_action(start_pos, Walk), Some(((2,13), Right)));
+    assert_eq!(start_pos, (Point(1,13),Right));
+    assert_eq!(read_puzzle(input).unwrap().get_start_state(), start_pos);
+    assert_eq!(read_puzzle("####\n#S.#\n#.E#\n#@##").err().unwrap().to_string(), "line 4, column 2: expected '#' or '.' or 'E' or 'S' or row");
+    assert_eq!(read_puzzle("####\n#S.#\n#.S#\n####").err().unwrap().to_string(), "line 3, column 3: 'S' is already at line 2, column 2");
+    assert_eq!(read_puzzle("####\n#S.#\n#..#\n####").err().unwrap().to_string(), "line 4: there is no 'E' in the map");
+    assert_eq!(puzzle.end, Point(13,1));
+    assert_eq!(puzzle.map.at(Point(13,1)), Space);
+    assert_eq!(puzzle.execute_action(start_pos, Walk), Some((Point(2,13), Right)));
     let r = puzzle.execute_action(start_pos, TurnRight).unwrap();
-    assert_eq!(r, ((1,13), Down));
+    assert_eq!(r, (Point(1,13), Down));
     assert_eq!(puzzle.execute_action(r, Walk), None);
 
-    assert_eq!(get_cost_of_state(&puzzle, ((13,1),Right)), 0);
-    assert_eq!(get_cost_of_state(&puzzle, ((13,1),Up)), 0);
-    let cost3 = get_cost_of_state(&puzzle, ((12,1),Right));
+    assert_eq!(get_cost_of_state(&puzzle, (Point(13,1),Right)), 0);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(13,1),Up)), 0);
+    let cost3 = get_cost_of_state(&puzzle, (Point(12,1),Right));
  //   puzzle.print_state();
     assert_eq!(cost3, 1);
-    assert_eq!(get_cost_of_state(&puzzle, ((12,1),Up)), 1001);
-    assert_eq!(get_cost_of_state(&puzzle, ((11,1),Up)), 1002);
-    assert_eq!(get_cost_of_state(&puzzle, ((11,1),Left)), 2002);
-    assert_eq!(get_cost_of_state(&puzzle, ((12,1),Right)), 1);
-    assert_eq!(get_cost_of_state(&puzzle, ((11,3),Right)), 4008);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(12,1),Up)), 1001);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(11,1),Up)), 1002);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(11,1),Left)), 2002);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(12,1),Right)), 1);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(11,3),Right)), 4008);
 
     assert_eq!(get_cost_of_state(&puzzle, puzzle.get_start_state()), 7036);
+    assert_eq!(lowest_score(&puzzle), 7036);
 
     assert_eq!(count_tiles_which_are_part_of_any_best_path(&puzzle), 45);
 
@@ -193,6 +288,8 @@ fn test_puzzle1() {
 
 #[test]
 fn test_puzzle2() {
+    use crate::maps::Point;
+    use crate::optimize::get_cost_of_state;
     let input=
 "#################
 #...#...#...#..E#
@@ -211,24 +308,33 @@ fn test_puzzle2() {
 #.#.#.#########.#
 #S#.............#
 #################";
-    let puzzle = Puzzle::read_input(input.split('\n'));
+    let puzzle = Puzzle::read_input(input.split('\n')).unwrap();
 
-    assert_eq!(get_cost_of_state(&puzzle, ((15,1),Right)), 0);
+    assert_eq!(get_cost_of_state(&puzzle, (Point(15,1),Right)), 0);
     assert_eq!(get_cost_of_state(&puzzle, puzzle.get_start_state()), 11048);
+    assert_eq!(lowest_score(&puzzle), 11048);
 
     assert_eq!(count_tiles_which_are_part_of_any_best_path(&puzzle), 64);
 
+    // the corridor search visits far fewer nodes than the tile search has states
+    let (graph, _, _) = best_paths_through_corridors(&puzzle);
+    let open_tiles = puzzle.map.area.all_positions().filter(|&pos| puzzle.map[pos] != Wall).count();
+    assert_eq!(open_tiles, 132); // 4 * 132 states of tile and direction
+    assert_eq!((graph.nodes.len(), graph.corridors.len()), (21, 50));
+
 }
 
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day16.txt");
+    let input = read_file_to_string("input/day16.txt");
 
-    let puzzle = Puzzle::read_input(lines.iter().map(|line| line.as_str()));
-    let costs = get_cost_of_state(&puzzle, puzzle.get_start_state());
+    let puzzle = expect_input(read_puzzle(&input), "input/day16.txt", &input);
+    let costs = lowest_score(&puzzle);
 
     println!("Day 16, Part 1: Lowest score to move from Start to End is {}", costs);
     println!("Day 16, Part 2: Number of tiles part of any best path is {}", count_tiles_which_are_part_of_any_best_path(&puzzle));
@@ -24,6 +24,9 @@ enum Opcode {
 
 use core::panic;
 
+use crate::helper::{InputError, Section, Sections};
+use crate::helper::parse::{fields, list_with_columns, register, ParseError};
+
 use Opcode::*;
 
 impl Opcode {
@@ -87,12 +90,12 @@ fn test_programs() {
 
     let state2 = ComputerState{a:10, b:0, c:0, ip:0};
     let program2 = program_from_str("5,0,5,1,5,4");
-    let output2 = run_program(&program2, state2);
+    let output2 = run_program(&program2, state2, &mut Recorder::disabled());
     assert_eq!(output2, vec![0,1,2]);
 
     let state3 = ComputerState{a:2024, b:0, c:0, ip:0};
     let program3 = program_from_str("0,1,5,4,3,0");
-    let output3 = run_program(&program3, state3);
+    let output3 = run_program(&program3, state3, &mut Recorder::disabled());
     assert_eq!(output3, vec![4,2,5,6,7,7,7,7,3,1,0]);
 
     let mut state4 = ComputerState{a:0, b:29, c:0, ip:0};
@@ -134,18 +137,32 @@ fn output_to_string(output:&Output) -> String {
     output_string_vec.join(",")
 }
 
-fn run_program(program:&Program, inital_state:ComputerState) -> Output {
+use crate::trace::{Event, Recorder};
+
+fn trace_state(step:usize, state:&ComputerState) -> Event {
+    Event::new(17, step).registers(&[("a", state.a as i64), ("b", state.b as i64), ("c", state.c as i64), ("ip", state.ip as i64)])
+}
+
+fn run_program(program:&Program, inital_state:ComputerState, recorder:&mut Recorder) -> Output {
     let mut state = inital_state.clone();
     let mut outputs:Output = Output::new();
+    let mut step = 0;
+    if recorder.enabled() { recorder.record(trace_state(step, &state)); }
     loop {
         if VERBOSE { println!("State: {:?}", state);}
         if state.ip >= program.len() {
             return outputs;
         }
         let (opcode, operand) = program[state.ip];
-        if let Some(output) = state.execute_instruction(opcode, operand) {
+        let output = state.execute_instruction(opcode, operand);
+        if let Some(output) = output {
             outputs.push(output);
         }
+        if recorder.enabled() {
+            step += 1;
+            let event = trace_state(step, &state).instruction(format!("{:?}", opcode), operand as i64);
+            recorder.record(match output { Some(output) => event.output(output as i64), None => event });
+        }
     }
 }
 
@@ -179,43 +196,67 @@ fn program_to_vec(program:&Program) -> Vec<Register> {
 }
 
 // str like "0,1,5,4,3,0"
+#[cfg(test)]
 fn program_from_str(str:&str) -> Program {
-    let mut j = str.split(',');
-    let mut program:Program = Program::new();
-    loop {
-        if let Some(opcode_str) = j.next() {
-            let operand_str = j.next().unwrap();
-            let opcode = Opcode::from_int(opcode_str.parse().unwrap());
-            let operand = operand_str.parse().unwrap();
-            program.push((opcode,operand));
-        } else {
-            return program;
+    program_from_vec(&list_with_columns::<Register>(str, ",").unwrap()).unwrap()
+}
+
+// values with their columns, see parse::list_with_columns
+fn program_from_vec(values:&[(usize, Register)]) -> Result<Program, ParseError> {
+    if !values.len().is_multiple_of(2) {
+        return Err(ParseError::new(1, format!("program has odd length {}", values.len())));
+    }
+    values.chunks(2).map(|chunk| {
+        let [(column, opcode), (_, operand)] = *chunk else { unreachable!() };
+        if opcode > 7 {
+            return Err(ParseError::new(column, format!("unexpected opcode {}", opcode)));
         }
+        Ok((Opcode::from_int(opcode), operand))
+    }).collect()
+}
+
+// Register A: 729
+fn read_register(line:&str, expected_name:&str) -> Result<Register, ParseError> {
+    let (name, register) = register::<Register>(line)?;
+    if name != expected_name {
+        return Err(ParseError::new(1, format!("expected '{}' instead of '{}'", expected_name, name)));
     }
+    Ok(register)
 }
 
-fn read_register(line:&str) -> Register {
-    // Register A: 729
-    let mut i = line.split(' ');
-    i.next().unwrap();
-    i.next().unwrap();
-    i.next().unwrap().parse().unwrap()
+fn read_registers(section:&Section) -> Result<ComputerState, InputError> {
+    let mut registers = Vec::new();
+    let names = ["Register A", "Register B", "Register C"];
+    for (index, line) in section.lines.iter().enumerate() {
+        let Some(expected_name) = names.get(index) else {
+            return Err(ParseError::new(1, format!("expected {} registers", names.len())).at_line(section.first_line + index));
+        };
+        registers.push(read_register(line, expected_name).map_err(|err| err.at_line(section.first_line + index))?);
+    }
+    let [a, b, c] = registers[..] else {
+        return Err(section.error(0, format!("expected {} registers instead of {}", names.len(), registers.len())));
+    };
+    Ok(ComputerState{a, b, c, ip:0})
 }
 
-fn read_program(line:&str) -> Program {
-    let mut i = line.split(' ');
-    i.next().unwrap();
-    program_from_str(i.next().unwrap())
+// Program: 0,1,5,4,3,0
+fn read_program(line:&str) -> Result<Program, ParseError> {
+    let [name, values] = fields::<2>(line, ": ")?;
+    if name != "Program" {
+        return Err(ParseError::new(1, format!("expected 'Program' instead of '{}'", name)));
+    }
+    let offset = name.len() + 2;
+    let numbers = list_with_columns::<Register>(values, ",").map_err(|err| err.shifted(offset))?;
+    program_from_vec(&numbers).map_err(|err| err.shifted(offset))
 }
 
-fn read_input<'a>(lines:impl Iterator<Item=&'a str> + Clone) -> (ComputerState, Program) {
-    let mut lines = lines.clone();
-    let register_a = read_register(lines.next().unwrap());
-    let register_b = read_register(lines.next().unwrap());
-    let register_c = read_register(lines.next().unwrap());
-    let newline = lines.next().unwrap(); assert!(newline.len() == 0);
-    let program = read_program(lines.next().unwrap());
-    (ComputerState{a:register_a, b:register_b, c:register_c, ip:0}, program)
+fn read_input<'a>(lines:impl Iterator<Item=&'a str>) -> Result<(ComputerState, Program), InputError> {
+    let sections = Sections::read(lines, &["registers", "program"], &[])?;
+    let program_section = sections.get("program");
+    program_section.expect_line_count(1)?;
+    let state = read_registers(sections.get("registers"))?;
+    let program = read_program(program_section.lines[0]).map_err(|err| err.at_line(program_section.first_line))?;
+    Ok((state, program))
 }
 
 fn is_program_cloning_itself(a:Register, program:&Program) -> bool {
@@ -257,11 +298,11 @@ Program: 0,3,5,4,3,0"
 #[test]
 fn test_example1() {
     let input = input1();
-    let (state,program) = read_input(input.split('\n'));
+    let (state,program) = read_input(input.split('\n')).unwrap();
     assert_eq!(state, ComputerState{a:729, b:0, c:0, ip:0});
     assert_eq!(program, vec![(ADV, 1), (OUT, 4), (JNZ, 0)]);
 
-    let output = run_program(&program, state.clone());
+    let output = run_program(&program, state.clone(), &mut Recorder::disabled());
     if VERBOSE {println!("Output: {:?}", output);}
     assert_eq!(output, vec![4,6,3,5,6,3,5,2,1,0]);
     assert_eq!(output_to_string(&output), "4,6,3,5,6,3,5,2,1,0");
@@ -269,11 +310,34 @@ fn test_example1() {
     assert!(run_program_check_output(&program, state, vec![4,6,3,5,6,3,5,2,1,0]));
 }
 
+#[test]
+fn test_read_input_error() {
+    let input = "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1,9,4,3,0";
+    let error = read_input(input.split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 5, column 14: unexpected opcode 9");
+
+    let input = "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1\nProgram: 3,0";
+    let error = read_input(input.split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 5: section 'program' has 2 lines instead of 1");
+
+    // the column does not depend on the width of the numbers before
+    let input = "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,17,9,4,3,0";
+    let error = read_input(input.split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 5, column 15: unexpected opcode 9");
+
+    let input = "Register A: 729\nRegister C: 0\nRegister B: 0\n\nProgram: 0,1";
+    let error = read_input(input.split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 2, column 1: expected 'Register B' instead of 'Register C'");
+
+    let error = read_input("Register A: 729\n\nProgram: 0,1".split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 1: expected 3 registers instead of 1");
+}
+
 #[test]
 fn test_example2() {
 
     let input = input2();
-    let (_state,program) = read_input(input.split('\n'));
+    let (_state,program) = read_input(input.split('\n')).unwrap();
 
     assert!(is_program_cloning_itself(117440, &program));
 
@@ -289,12 +353,14 @@ fn test_example2() {
 /// Puzzle
 //////////////////////////////////////////
 
-pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day17.txt");
-    let (initial_state,program) = read_input(lines.iter().map(|line| line.as_str()));
+use crate::helper::{expect_input, read_file_to_string};
+
+pub fn puzzle(recorder:&mut Recorder) {
+    let input = read_file_to_string("input/day17.txt");
+    let (initial_state,program) = expect_input(read_input(input.lines()), "input/day17.txt", &input);
 
     if VERBOSE {println!("Day 16, Debug program = {:?}", program);}
-    let output1 = run_program(&program, initial_state.clone());
+    let output1 = run_program(&program, initial_state.clone(), recorder);
     println!("Day 16, Part 1: Output of program is {:?}", output_to_string(&output1));
 
     let a = find_first_cloning_a(&program);
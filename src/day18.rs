@@ -1,91 +1,73 @@
-use crate::maps::Direction;
-use crate::optimize::get_cost_of_state;
-use crate::maps::FromChar;
-use crate::maps::Position;
-use crate::maps::PixelMap;
-use crate::optimize::Problem;
+use crate::maps::TryFromChar;
+use crate::maps::{Point, Position};
+use std::collections::HashSet;
+
+use crate::maps::{distance_field_to, Area, DistanceField, Grid, SparseMap};
+use crate::helper::InputError;
+use crate::helper::parse::{pair, parse_lines};
 type Positions = Vec<Position>;
 
 const VERBOSE:bool = false;
 
-fn parse_input(lines:Vec<&str>) -> Positions {
-    lines.iter().map(|&line|
-        {
-            let mut split = line.split(',');
-            (split.next().unwrap().parse().unwrap(),
-             split.next().unwrap().parse().unwrap())
-        }
-    ).collect()
+// "5,4"
+fn parse_input(input:&str) -> Result<Positions, InputError> {
+    parse_lines(input, |line| pair::<usize>(line, ",").map(|(x, y)| Point(x, y)))
 }
 
-impl FromChar for bool {
-    fn from_char(c:char) -> Self {
+impl TryFromChar for bool {
+    fn try_from_char(c:char) -> Option<Self> {
         match c {
-            '.' => false,
-            '#' => true,
-            _ => unreachable!()
+            '.' => Some(false),
+            '#' => Some(true),
+            _ => None
         }
     }
 }
 
-fn drop_n(map:&mut PixelMap<bool>, positions:&Positions, n:usize) {
+fn drop_n<G:Grid<Element=bool>>(map:&mut G, positions:&Positions, n:usize) {
     let mut iter = positions.iter();
     for _ in 0..n {
         let pos = iter.next().unwrap();
-        map.set_at(*pos, true);
+        map.set(*pos, true);
     }
 }
 
-struct Maze {
-    map:PixelMap<bool>
-}
+const START:Position = Point(0,0);
 
-impl Maze {
-    const START_STATE:Position = (0,0);
+// the exit is in the bottom right corner
+fn exit<G:Grid<Element=bool>>(map:&G) -> Position {
+    let area = map.bounds().expect("The maze has no exit");
+    Point(area.width - 1, area.height - 1)
 }
 
-impl Problem for Maze {
-    type State = Position;
-    type Action = Direction;
-    fn is_end_state(&self, state:&Self::State) -> bool {
-        state.0 == self.map.width() - 1 &&
-        state.1 == self.map.height() - 1
-    }
-    fn execute_action(&self, before:Self::State, action:Self::Action) -> Option<Self::State> {
-        if let Some(newpos) = self.map.area.step(before, action) {
-            if self.map.at(newpos) {
-                None
-            } else {
-                Some(newpos)
-            }
-        } else {
-            None
-        }
-    }
-
-    fn cost(&self, _action:Self::Action) -> crate::optimize::Cost {
-        1
-    }
+// corrupted positions are true
+fn distances_to_exit<G:Grid<Element=bool>>(map:&G) -> DistanceField {
+    let exit = exit(map);
+    distance_field_to(map, [START], |_, to| map.get(to) != Some(true), |pos| pos == exit)
+}
 
-    fn all_actions(&self) -> Vec<Direction> {
-        vec![
-            Direction::Up,
-            Direction::Down,
-            Direction::Left,
-            Direction::Right]
-    }
+// None if the exit cannot be reached
+fn steps_to_exit<G:Grid<Element=bool>>(map:&G) -> Option<u32> {
+    distances_to_exit(map).distances[exit(map)]
+}
 
+fn path_to_exit<G:Grid<Element=bool>>(map:&G) -> Option<Vec<Position>> {
+    distances_to_exit(map).path_to(exit(map))
 }
 
-fn get_blocking_position(initialmap:PixelMap<bool>, positions:&Positions) -> Position {
-    let mut problem = Maze{map:initialmap};
+fn get_blocking_position<G:Grid<Element=bool>>(mut map:G, positions:&Positions) -> Position {
+    // only a byte on the current path can block the way, so search again only then
+    let mut path:HashSet<Position> = path_to_exit(&map).expect("The exit cannot be reached").into_iter().collect();
     for &pos in positions {
-        problem.map.set_at(pos, true);
-        let cost = get_cost_of_state(&problem, Maze::START_STATE);
-        if VERBOSE { println!("Cost = {}", cost);}
-        if cost == u32::MAX {
-            return pos
+        map.set(pos, true);
+        if !path.contains(&pos) {
+            continue;
         }
+        let Some(new_path) = path_to_exit(&map) else {
+            return pos
+        };
+        if VERBOSE { println!("Steps = {}", new_path.len() - 1);}
+        path = new_path.into_iter().collect();
     }
     unreachable!();
 }
@@ -123,41 +105,51 @@ fn input1() -> &'static str {
 #[test]
 fn test_example1() {
     let input = input1();
-    let lines = input.split('\n').collect();
-    let positions = parse_input(lines);
-    assert_eq!(positions[3], (3,0));
+    let positions = parse_input(input).unwrap();
+    assert_eq!(positions[3], Point(3,0));
+    assert_eq!(parse_input("5,4\n4 2").err().unwrap().to_string(), "line 2, column 4: expected 2 parts separated by ',', found 1");
 
-    let initialmap = PixelMap::<bool>::new(7,7,false);
+    let initialmap = SparseMap::<bool>::new(Area::new(7,7));
 
-    let problem0 = Maze{map:initialmap.clone()};
-    let cost0 = get_cost_of_state(&problem0, Maze::START_STATE);
-    assert_eq!(cost0, 6+6);
+    assert_eq!(steps_to_exit(&initialmap), Some(6+6));
 
     let mut map = initialmap.clone();
     drop_n(&mut map, &positions, 12);
-    assert_eq!(map.at((3,0)), true);
-    assert_eq!(map.at((1,2)), false);
+    assert_eq!(map.get(Point(3,0)), Some(true));
+    assert_eq!(map.get(Point(1,2)), None);
+
+    assert_eq!(steps_to_exit(&map), Some(22));
+    let path = path_to_exit(&map).unwrap();
+    assert_eq!(path.len(), 22 + 1);
+    assert_eq!((path[0], path[22]), (START, Point(6,6)));
+    assert!(path.iter().all(|&pos| map.get(pos).is_none()));
 
-    let problem1 = Maze{map};
-    let cost1 = get_cost_of_state(&problem1, Maze::START_STATE);
-    assert_eq!(cost1, 22);
+    assert_eq!(get_blocking_position(initialmap, &positions), Point(6,1));
 
-    assert_eq!(get_blocking_position(initialmap, &positions), (6,1));
+    // the same with a dense map
+    use crate::maps::PixelMap;
+    let densemap = PixelMap::<bool>::new(7,7,false);
+    let mut map = densemap.clone();
+    drop_n(&mut map, &positions, 12);
+    assert_eq!(map.get(Point(1,2)), Some(false));
+    assert_eq!(steps_to_exit(&map), Some(22));
+    assert_eq!(get_blocking_position(densemap, &positions), Point(6,1));
 }
 
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day18.txt");
-    let positions = parse_input(lines.iter().map(|line| line.as_str()).collect());
-    let initialmap = PixelMap::<bool>::new(71,71,false);
+    let input = read_file_to_string("input/day18.txt");
+    let positions = expect_input(parse_input(&input), "input/day18.txt", &input);
+    let initialmap = SparseMap::<bool>::new(Area::new(71,71));
 
     let mut map1 = initialmap.clone();
     drop_n(&mut map1, &positions, 1024);
-    let problem = Maze{map:map1};
-    let cost = get_cost_of_state(&problem, Maze::START_STATE);
+    let cost = steps_to_exit(&map1).expect("The exit cannot be reached");
 
     println!("Day 16, Part 1: Minimum number of steps to reach output after 1024 bytes is {}", cost);
 
@@ -1,6 +1,7 @@
 const VERBOSE:bool=false;
 use regex::Regex;
 
+use crate::helper::{InputError, Sections};
 use crate::optimize::{count_all_best_paths, ActionTrait, Problem};
 #[cfg(test)]
 use crate::optimize::get_all_best_paths;
@@ -38,23 +39,32 @@ impl DesignChecker {
     }
 }
 
-fn read_input(input:Vec<String>) -> (Towels, Designs) {
-    let mut iter = input.iter();
-    let towels_line = iter.next().unwrap();
-    let towels = towels_line.split(", ").map(|str| str.to_string()).collect::<Towels>();
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::parse::parse_rule;
 
-    assert_eq!(iter.next().unwrap(), "");
+#[derive(Parser)]
+#[grammar = "../grammar/day19.pest"]
+struct Day19Parser;
 
-    let mut designs = Designs::new();
-    for line in iter {
-        designs.push(line.clone());
-    }
+// towels or designs
+fn build_stripes_list(list_rule:Pair<'_, Rule>) -> Vec<String> {
+    list_rule.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::stripes)
+        .map(|pair| pair.as_str().to_string())
+        .collect()
+}
 
-    (towels, designs)
+fn read_input<'a>(input:impl Iterator<Item=&'a str>) -> Result<(Towels, Designs), InputError> {
+    let sections = Sections::read(input, &["towels", "designs"], &[])?;
+    sections.get("towels").expect_line_count(1)?;
+    let towels = sections.get("towels").parse(|text| Ok(build_stripes_list(parse_rule::<Day19Parser, _>(Rule::towels, text)?)))?;
+    let designs = sections.get("designs").parse(|text| Ok(build_stripes_list(parse_rule::<Day19Parser, _>(Rule::designs, text)?)))?;
+    Ok((towels, designs))
 }
 
 #[cfg(test)]
-fn input1() -> Vec<String> {
+fn input1() -> &'static str {
 "r, wr, b, g, bwu, rb, gb, br
 
 brwrr
@@ -64,15 +74,16 @@ rrbgbr
 ubwu
 bwurrg
 brgr
-bbrgwb".split('\n').map(|str| str.to_string()).collect::<Vec<_>>()
+bbrgwb"
 }
 
 #[test]
 fn test_example1() {
-    let (towels, designs) = read_input(input1());
+    let (towels, designs) = read_input(input1().split('\n')).unwrap();
     assert_eq!(towels.len(), 8);
     assert_eq!(&towels[4], "bwu");
     assert_eq!(designs.len(), 8);
+    assert_eq!(read_input("r, wr, bx\n\nbrwrr".split('\n')).err().unwrap().to_string(), "line 1, column 9: expected ', ' or 'b' or 'g' or 'r' or 'u' or 'w' or stripes");
     assert_eq!(&designs[4], "ubwu");
 
     let checker = DesignChecker::new(towels);
@@ -220,9 +231,11 @@ fn test_part2()
 /// Puzzle
 //////////////////////////////////////////
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day19.txt");
-    let (towels, designs) = read_input(lines);
+    let input = read_file_to_string("input/day19.txt");
+    let (towels, designs) = expect_input(read_input(input.lines()), "input/day19.txt", &input);
 
     let checker = DesignChecker::new(towels.clone());
     let design_count = designs.len();
@@ -1,4 +1,7 @@
 
+use crate::helper::InputError;
+use crate::helper::parse::{list, parse_lines, ParseError};
+
 type Level = i32;
 
 //////////////////////////////////////////
@@ -62,7 +65,7 @@ fn test_is_safe() {
 1 3 2 4 5
 8 6 4 4 1
 1 3 6 7 9";
-    let reports = parse_reports(input1.split("\n"));
+    let reports = parse_reports(input1).unwrap();
     assert_eq!(reports[0].is_safe(false), true);
     assert_eq!(reports[1].is_safe(false), false);
     assert_eq!(reports[2].is_safe(false), false);
@@ -77,31 +80,23 @@ fn test_is_safe() {
     assert_eq!(reports[4].is_safe(true), true);
     assert_eq!(reports[5].is_safe(true), true);
 
-    assert_eq!(parse_report("100 1 2 3").is_safe(true), true);
-    assert_eq!(parse_report("100 1 2 3 4").is_safe(true), true);
-    assert_eq!(parse_report("1 2 3 99").is_safe(true), true);
-    assert_eq!(parse_report("40 41 43 44 47 47").is_safe(true), true);
-    assert_eq!(parse_report("1 3 2 3 4").is_safe(true), true);
+    assert_eq!(parse_report("100 1 2 3").unwrap().is_safe(true), true);
+    assert_eq!(parse_report("100 1 2 3 4").unwrap().is_safe(true), true);
+    assert_eq!(parse_report("1 2 3 99").unwrap().is_safe(true), true);
+    assert_eq!(parse_report("40 41 43 44 47 47").unwrap().is_safe(true), true);
+    assert_eq!(parse_report("1 3 2 3 4").unwrap().is_safe(true), true);
 }
 
 //////////////////////////////////////////
 /// Parsing
 //////////////////////////////////////////
 
-fn parse_report(line:&str) -> Report {
-    let mut levels:Vec<Level> = Vec::new();
-    for level_str in line.split(" ") {
-        levels.push(level_str.parse::<Level>().unwrap());
-    }
-    Report { levels:levels }
+fn parse_report(line:&str) -> Result<Report, ParseError> {
+    Ok(Report { levels:list::<Level>(line, " ")? })
 }
 
-fn parse_reports<'a>(lines:impl Iterator<Item=&'a str>) -> Reports {
-    let mut reports:Vec<Report> = Vec::new();
-    for line in lines {
-        reports.push(parse_report(line));
-    }
-    reports
+fn parse_reports(input:&str) -> Result<Reports, InputError> {
+    parse_lines(input, parse_report)
 }
 
 #[test]
@@ -113,7 +108,7 @@ fn test_parse() {
 1 3 2 4 5
 8 6 4 4 1
 1 3 6 7 9";
-    let reports = parse_reports(input1.split("\n"));
+    let reports = parse_reports(input1).unwrap();
     assert_eq!(reports,
         vec![
             Report { levels:vec![7,6,4,2,1] },
@@ -123,22 +118,18 @@ fn test_parse() {
             Report { levels:vec![8,6,4,4,1] },
             Report { levels:vec![1,3,6,7,9] }
         ]);
+    assert_eq!(parse_reports("7 6 4\n1 2 x").unwrap_err().to_string(), "line 2, column 5: 'x' is no i32");
 }
 
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 pub fn puzzle() {
-    let file = File::open("input/day2.txt").expect("Could not open input/day2.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
-    let reports = parse_reports(lines.iter().map( |line| line.as_str() ));
+    let input = read_file_to_string("input/day2.txt");
+    let reports = expect_input(parse_reports(&input), "input/day2.txt", &input);
 
     let safe_report_count1:u32 = reports.iter().map( |report| match report.is_safe(false) { true => 1, false => 0 }).sum();
     println!("Day 2, Part 1: Number of safe reports is {} of {}", safe_report_count1, reports.len());
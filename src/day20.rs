@@ -1,12 +1,9 @@
 use crate::maps::Position;
 use crate::maps::Direction;
-use crate::optimize::get_cost_cache;
-use crate::optimize::get_cost_of_state;
-use crate::optimize::Problem;
+use crate::maps::{distance_field, junction_graph};
+use crate::render::{Color, Renderer, Style};
 use Direction::*;
 
-use std::collections::HashMap;
-
 const VERBOSE:bool = false;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -19,14 +16,25 @@ enum MapElement {
 
 use MapElement::*;
 
-impl crate::maps::FromChar for MapElement {
-    fn from_char(c:char) -> Self {
+impl crate::maps::TryFromChar for MapElement {
+    fn try_from_char(c:char) -> Option<Self> {
         match c {
-            '.' => Space,
-            '#' => Wall,
-            'S' => Start,
-            'E' => End,
-            _ => panic!("Unexpected character {} for TestEnum", c)
+            '.' => Some(Space),
+            '#' => Some(Wall),
+            'S' => Some(Start),
+            'E' => Some(End),
+            _ => None
+        }
+    }
+}
+
+impl crate::maps::ToChar for MapElement {
+    fn to_char(self) -> char {
+        match self {
+            Space => '.',
+            Wall => '#',
+            Start => 'S',
+            End => 'E'
         }
     }
 }
@@ -36,47 +44,11 @@ type Map = PixelMap<MapElement>;
 
 
 
-use crate::optimize::ActionTrait;
-
-impl ActionTrait for Direction {
-}
-
 type Cost = u32;
 
-struct ShortestPathProblem<'a> {
-    map:&'a Map,
-    start:Position,
-    end:Position
-}
-
-impl<'a> Problem for ShortestPathProblem<'a> {
-
-    type State = Position;
-    type Action = Direction;
-
-    fn is_end_state(&self, state:&Self::State) -> bool {
-        *state == self.end
-    }
-
-    // none means action cannot be executed
-    fn execute_action(&self, before:Position, action:Direction) -> Option<Position> {
-        let after = self.map.area.step(before, action);
-        if after.is_none() { return None; }
-        let after = after.unwrap();
-        if self.map.at(after) == Wall { return None; }
-        return Some(after);
-    }
-
-    fn cost(&self, _action:Self::Action) -> crate::optimize::Cost {
-        1
-    }
-
-    fn all_actions(&self) -> Vec<Self::Action> {
-        vec![
-            Up, Right, Down, Left
-        ]
-    }
-
+// one step on the track
+fn passable(map:&Map, _from:Position, to:Position) -> bool {
+    map.at(to) != Wall
 }
 
 impl Puzzle {
@@ -97,45 +69,51 @@ impl Puzzle {
 
 }
 
+// the race track is one long corridor
 fn cost_of_shortest_path(map:&Map, start:Position, end:Position) -> Cost {
-    let problem = ShortestPathProblem{map, start, end};
-    get_cost_of_state(&problem, problem.start)
+    let graph = junction_graph(map, |element| element != Wall, [start, end]);
+    let paths = graph.cheapest_paths(graph.node_at(start).unwrap(), |_, corridor| corridor.length as Cost);
+    paths.cost_to(&graph, graph.node_at(end).unwrap()).expect("There is no path from start to end")
 }
 
 struct Puzzle {
     // todo: we could reference an existing map
-    map:Map,
-    cost_map:HashMap<Position,Cost>,
+    map:Map, // Start and End are replaced with Space
+    start:Position,
+    end:Position,
+    cost_map:PixelMap<Option<Cost>>, // from each position to the end
     cost_of_path_without_cheating:Cost  // not really required!
 }
 
 type Cheat = (/*from:*/Position, /*to:*/Position, /*cost_for_cheat:*/Cost);
 
 impl Puzzle {
-    fn from<'a>(map_lines:impl Iterator<Item=&'a str>) -> Puzzle {
-        let map = Map::from_strings(map_lines);
-        let cost_of_path_without_cheating = cost_of_shortest_path(&map, map.find_first(Start).unwrap(), map.find_first(End).unwrap());
-        Puzzle {
+    fn from<'a>(map_lines:impl Iterator<Item=&'a str>) -> Result<Puzzle, InputError> {
+        let mut map = Map::try_from_strings(map_lines)?;
+        let [start, end] = map.extract_markers([Start, End], Space)?;
+        let cost_of_path_without_cheating = cost_of_shortest_path(&map, start, end);
+        Ok(Puzzle {
             map,
-            cost_map:HashMap::new(),
+            start,
+            end,
+            cost_map:PixelMap::new(0, 0, None),
             cost_of_path_without_cheating
-        }
+        })
     }
 
     fn create_cost_map(&mut self) {
-        let reverse_start = self.map.find_first(End).unwrap();
-        let reverse_end = self.map.find_first(Start).unwrap();
-
-        let problem = ShortestPathProblem{map:&self.map, start:reverse_start, end:reverse_end};
-        self.cost_map = get_cost_cache(&problem, reverse_start);
+        let map = &self.map;
+        self.cost_map = distance_field(map, [self.end], |from, to| passable(map, from, to)).distances;
 
         if VERBOSE {
-            for (pos, cost) in &self.cost_map {
-                println!("({},{})->{}", pos.0, pos.1, cost);
+            for pos in self.cost_map.area.all_positions() {
+                if let Some(cost) = self.cost_map[pos] {
+                    println!("({},{})->{}", pos.0, pos.1, cost);
+                }
             }
         }
 
-        assert_eq!(self.cost_of_path_without_cheating, *self.cost_map.get(&self.map.find_first(Start).unwrap()).unwrap());
+        assert_eq!(Some(self.cost_of_path_without_cheating), self.cost_map[self.start]);
     }
 
     fn get_all_cheats_part1(&self) -> Vec<Cheat> {
@@ -158,16 +136,17 @@ impl Puzzle {
     const CHEAT_MAX_LEN_PART_2:u32 = 20;
 
     fn get_all_cheats_part2(&self) -> Vec<Cheat> {
+        let max_len = Self::CHEAT_MAX_LEN_PART_2 as i32;
         let mut cheats:Vec<Cheat> = Vec::new();
         for start_state in self.map.area.all_positions() {
             if self.map.at(start_state) == Wall { continue; }
-            for length in 2..Self::CHEAT_MAX_LEN_PART_2+1 {
-                // for length 3 we want to have [(-3,0),(-2,1),(-1,2),(0,3),(1,2),(2,1)] - because (-3,0) == (3,0)
-                for dx in -(length as i32)..(length as i32) {
-                    let dy = (length as i32) - dx.abs();
-                    let end_state = self.map.area.position_add(start_state, dx, dy);
-                    if end_state.is_none() { continue;}
-                    let end_state = end_state.unwrap();
+            // the cheat is indirectional, so only the ends below the start and left of it in the same row
+            for dy in 0..=max_len {
+                for dx in -max_len..=max_len {
+                    if dy == 0 && dx >= 0 { continue; }
+                    let Some(end_state) = self.map.area.position_add(start_state, dx, dy) else { continue; };
+                    let length = start_state.manhattan(end_state) as Cost;
+                    if !(2..=Self::CHEAT_MAX_LEN_PART_2).contains(&length) { continue; }
                     if self.map.at(end_state) == Wall { continue; }
                     if VERBOSE { println!("  Cheat from ({},{}) to ({}, {}) with length {}", start_state.0, start_state.1, end_state.0, end_state.1, length );}
                     cheats.push((start_state, end_state, length));
@@ -177,15 +156,19 @@ impl Puzzle {
         cheats
     }
 
+    fn get_saving_of_cheat(&self, cheat:&Cheat) -> Cost {
+        let cost_of_shortest_path =
+            self.cost_map[cheat.0].unwrap().abs_diff(
+                self.cost_map[cheat.1].unwrap()
+            );
+        cost_of_shortest_path - cheat.2
+    }
+
     fn get_savings_of_cheats(&self, cheats:&Vec<Cheat>, minimum_saving:Cost) -> Vec<Cost> {
         cheats.iter().map(
             |cheat|
             {
-                let cost_of_shortest_path =
-                    self.cost_map.get(&cheat.0).unwrap().abs_diff(
-                        *self.cost_map.get(&cheat.1).unwrap()
-                    );
-                let saving = cost_of_shortest_path - cheat.2;
+                let saving = self.get_saving_of_cheat(cheat);
                 if VERBOSE { println!("  Cheat from ({},{}) to ({}, {}) with length {}: Saving = {}", cheat.0.0, cheat.0.1, cheat.1.0, cheat.1.1, cheat.2, saving );}
                 saving
             }
@@ -193,12 +176,38 @@ impl Puzzle {
         .collect()
     }
 
+    // the starts (green) and ends (red) of the cheats saving at least minimum_saving
+    fn print_cheats(&self, cheats:&[Cheat], minimum_saving:Cost) {
+        let good_cheats:Vec<&Cheat> = cheats.iter().filter(|cheat| self.get_saving_of_cheat(cheat) >= minimum_saving).collect();
+        Renderer::new(&self.map)
+            .colors(|_, element| if element == Wall { Some(Style::foreground(Color::Blue)) } else { None })
+            .highlight(good_cheats.iter().map(|cheat| cheat.0), Style::background(Color::Green))
+            .highlight(good_cheats.iter().map(|cheat| cheat.1), Style::background(Color::Red))
+            .println();
+    }
+
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
 
+#[derive(Parser)]
+#[grammar = "../grammar/day20.pest"]
+struct Day20Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Puzzle, InputError> {
+    Puzzle::from(map_rows(file_rule, Rule::row)?.into_iter())
+}
+
+fn read_puzzle(input:&str) -> Result<Puzzle, InputError> {
+    build_file(parse_rule::<Day20Parser, _>(Rule::file, input)?)
+}
 
 #[test]
 fn test_puzzle1() {
+    use crate::maps::Point;
     let input=
 "###############
 #...#...#.....#
@@ -216,20 +225,21 @@ fn test_puzzle1() {
 #...#...#...###
 ###############";
     let puzzle = {
-        let mut p = Puzzle::from(input.split('\n'));
+        let mut p = Puzzle::from(input.split('\n')).unwrap();
         p.create_cost_map();
         p
     };
-    let start_pos = puzzle.map.find_first(Start).unwrap();
-    let problem = ShortestPathProblem{
-        map: &puzzle.map,
-        start: start_pos,
-        end: puzzle.map.find_first(End).unwrap()
-    };
-    assert_eq!(problem.execute_action(start_pos, Up), Some((1,2)));
-    assert_eq!(problem.execute_action(start_pos, Right), None);
-    assert_eq!(problem.execute_action(start_pos, Left), None);
-    assert_eq!(puzzle.execute_cheat(start_pos, Right), Some((3,3)));
+    assert_eq!(read_puzzle(input).unwrap().cost_of_path_without_cheating, puzzle.cost_of_path_without_cheating);
+    assert_eq!(read_puzzle("####\n#S.#\n#.E\n####").err().unwrap().to_string(), "line 3, column 4: row has 3 characters instead of 4");
+    assert_eq!(read_puzzle("####\n#S.#\n#..#\n####").err().unwrap().to_string(), "line 4: there is no 'E' in the map");
+    let start_pos = puzzle.start;
+    assert_eq!(puzzle.map.at(start_pos), Space);
+    assert!(passable(&puzzle.map, start_pos, Point(1,2)));
+    assert!(!passable(&puzzle.map, start_pos, Point(2,3)));
+    assert!(!passable(&puzzle.map, start_pos, Point(0,3)));
+    assert_eq!(puzzle.cost_map[start_pos], Some(84));
+    assert_eq!(puzzle.cost_map[Point(2,3)], None);
+    assert_eq!(puzzle.execute_cheat(start_pos, Right), Some(Point(3,3)));
     assert_eq!(puzzle.execute_cheat(start_pos, Left), None);
 
     assert_eq!(puzzle.cost_of_path_without_cheating, 84);
@@ -267,15 +277,22 @@ fn test_puzzle1() {
 /// Puzzle
 //////////////////////////////////////////
 
+use std::time::Instant;
+
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day20.txt");
+    let input = read_file_to_string("input/day20.txt");
 
-    let mut puzzle = Puzzle::from(lines.iter().map(|line| line.as_str()));
+    let mut puzzle = expect_input(read_puzzle(&input), "input/day20.txt", &input);
     if VERBOSE { println!("Day 20: Full path is {} picoseconds", puzzle.cost_of_path_without_cheating)}
+    let start = Instant::now();
     puzzle.create_cost_map();
+    let duration_cost_map = start.elapsed();
     let all_cheats1 = puzzle.get_all_cheats_part1();
-    println!("Number of cheats (length=2) is {}", all_cheats1.len());
+    println!("Number of cheats (length=2) is {} (cost map calculated in {} µs)", all_cheats1.len(), duration_cost_map.as_micros());
     let path_savings1 = puzzle.get_savings_of_cheats(&all_cheats1, 100);
+    if VERBOSE { puzzle.print_cheats(&all_cheats1, 100); }
 
     println!("Day 20, Part 1: Number of cheats (length=2) saving at least 100 picoseconds is {}", path_savings1.len());
 
@@ -1,28 +1,28 @@
 
 use std::{char, collections::HashMap};
 
-use crate::maps::{left, right, up, down, Position};
+use crate::maps::{left, right, up, down, Point, Position};
 
 // 0 1 2 3 4 5 6 7 8 9 A
 type NumericKey = char;
 
-const NUMERIC_KEY_START : Position = (2,3);
-const NUMERIC_KEY_GAP   : Position = (0,3);
+const NUMERIC_KEY_START : Position = Point(2,3);
+const NUMERIC_KEY_GAP   : Position = Point(0,3);
 
 fn numeric_key_to_position(numeric_key:NumericKey) -> Position {
     match numeric_key {
-        '7' => (0,0),
-        '8' => (1,0),
-        '9' => (2,0),
-        '4' => (0,1),
-        '5' => (1,1),
-        '6' => (2,1),
-        '1' => (0,2),
-        '2' => (1,2),
-        '3' => (2,2),
-
-        '0' => (1,3),
-        'A' => (2,3),
+        '7' => Point(0,0),
+        '8' => Point(1,0),
+        '9' => Point(2,0),
+        '4' => Point(0,1),
+        '5' => Point(1,1),
+        '6' => Point(2,1),
+        '1' => Point(0,2),
+        '2' => Point(1,2),
+        '3' => Point(2,2),
+
+        '0' => Point(1,3),
+        'A' => Point(2,3),
         other => panic!("Unexpected numeric key {}", other)
     }
 }
@@ -30,16 +30,16 @@ fn numeric_key_to_position(numeric_key:NumericKey) -> Position {
 // ^ < v > A
 type DirectionKey = char;
 
-const DIRECTION_KEY_START : Position = (2,0);
-const DIRECTION_KEY_GAP   : Position = (0,0);
+const DIRECTION_KEY_START : Position = Point(2,0);
+const DIRECTION_KEY_GAP   : Position = Point(0,0);
 
 fn direction_key_to_position(direction_key:DirectionKey) -> Position {
     match direction_key {
-        '^' => (1,0),
-        'A' => (2,0),
-        '<' => (0,1),
-        'v' => (1,1),
-        '>' => (2,1),
+        '^' => Point(1,0),
+        'A' => Point(2,0),
+        '<' => Point(0,1),
+        'v' => Point(1,1),
+        '>' => Point(2,1),
         other => panic!("Unexpected direction key {}", other)
     }
 }
@@ -98,10 +98,10 @@ fn vec_to_str(vec:&Vec<DirectionKey>) -> String {
 
 #[test]
 fn test_positions_to_all_possible_keys() {
-    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys((2,2),(2,2),(0,0))),vec!["A"]);
-    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys((2,2),(3,2),(0,0))),vec![">A"]);
-    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys((2,2),(3,3),(0,0))),vec![">vA","v>A"]);
-    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys((2,2),(3,3),(2,3))),vec![">vA"]);
+    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys(Point(2,2),Point(2,2),Point(0,0))),vec!["A"]);
+    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys(Point(2,2),Point(3,2),Point(0,0))),vec![">A"]);
+    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys(Point(2,2),Point(3,3),Point(0,0))),vec![">vA","v>A"]);
+    assert_eq!(vecvec_to_strvec(positions_to_all_possible_keys(Point(2,2),Point(3,3),Point(2,3))),vec![">vA"]);
 }
 
 /*
@@ -271,6 +271,33 @@ fn best_keylen_for_numeric_keys_n(numeric_keys:&Vec<NumericKey>, n:u32) -> usize
     keylen
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::parse_rule;
+
+#[derive(Parser)]
+#[grammar = "../grammar/day21.pest"]
+struct Day21Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Vec<String> {
+    file_rule.into_inner()
+        .filter(|pair| pair.as_rule() == Rule::code)
+        .map(|pair| pair.as_str().to_string())
+        .collect()
+}
+
+fn read_codes(input:&str) -> Result<Vec<String>, InputError> {
+    Ok(build_file(parse_rule::<Day21Parser, _>(Rule::file, input)?))
+}
+
+#[test]
+fn test_read_codes() {
+    assert_eq!(read_codes("029A\n980A\n"), Ok(vec!["029A".to_string(), "980A".to_string()]));
+    assert_eq!(read_codes("029A\n98A").unwrap_err().to_string(), "line 2, column 3: expected code");
+}
+
+// the code is checked by the grammar
 fn calculate_complexity(code:&str, keylen:usize) -> u64 {
     let code_int:u64 = code[0..3].parse().unwrap();
     code_int * keylen as u64
@@ -327,8 +354,11 @@ fn test() {
 
 use std::time::Instant;
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day21.txt");
+    let input = read_file_to_string("input/day21.txt");
+    let lines = expect_input(read_codes(&input), "input/day21.txt", &input);
 
     let results = lines.iter().
         map(|code| (code, best_keys_for_numeric_keys_n(&code.chars().collect(),2)));
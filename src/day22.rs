@@ -1,3 +1,6 @@
+use crate::helper::InputError;
+use crate::helper::parse::{parse_rule, value};
+
 type Number = u64;
 
 fn mix(a: Number, b: Number) -> Number {
@@ -174,15 +177,40 @@ fn test_iterator() {
 
 }
 
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar = "../grammar/day22.pest"]
+struct Day22Parser;
+
+fn read_secret(line:&str) -> Result<Number, InputError> {
+    let pair = parse_rule::<Day22Parser, _>(Rule::secret, line)?;
+    value::<Number>(pair.as_str()).map_err(|err| err.at_pair(&pair))
+}
+
+#[test]
+fn test_read_secret() {
+    assert_eq!(read_secret("2024"), Ok(2024));
+    assert_eq!(read_secret("20x4").unwrap_err().to_string(), "line 1, column 3: expected end of line");
+}
+
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day22.txt");
+    let mut reader = crate::helper::LineReader::open("input/day22.txt");
 
-    let initials = lines.into_iter().map(|line| line.parse::<Number>().unwrap());
-    let sum:Number = initials.map(|initial| secret(initial).nth(2000-1).unwrap()).sum();
+    let mut sum:Number = 0;
+    while let Some((line_number, line)) = reader.next_line() {
+        let initial = read_secret(line).map_err(|err| err.after_line(line_number))
+            .unwrap_or_else(|err| panic!("Could not read input/day22.txt, {}", err.render_line(line)));
+        sum += secret(initial).nth(2000-1).unwrap();
+    }
+    if reader.next_section() {
+        let (line_number, line) = reader.next_line().unwrap();
+        panic!("Could not read input/day22.txt, {}", InputError::new(line_number, "unexpected section after the secrets".to_string()).render_line(line));
+    }
 
     println!("Day 22, Part 1: Sum of 2000th secret is {}", sum);
 
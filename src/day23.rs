@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use crate::helper::InputError;
+
 #[derive(PartialEq, PartialOrd, Eq, Ord, Hash, Clone, Copy)]
 struct Computer
 {
@@ -44,29 +46,32 @@ impl Network {
     }
 }
 
-fn read_input<'a>(lines:impl Iterator<Item=&'a str>) -> Network {
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::parse::parse_rule;
+
+#[derive(Parser)]
+#[grammar = "../grammar/day23.pest"]
+struct Day23Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Network {
     let mut computers:HashSet<Computer> = HashSet::new();
-    //let mut links:HashMap<Computer, Vec<Computer>> = HashMap::new();
     let mut links:Vec<(Computer, Computer)> = Vec::new();
-    for line in lines {
-        let mut parts = line.split('-');
-        let computer1 = Computer::from_str(parts.next().unwrap());
-        let computer2 = Computer::from_str(parts.next().unwrap());
-        assert!(parts.next().is_none());
-        if computers.insert(computer1) {
-            //links.insert(computer1, Vec::new());
-        }
-        if computers.insert(computer2) {
-            //links.insert(computer2, Vec::new());
-        }
+    for link in file_rule.into_inner().filter(|pair| pair.as_rule() == Rule::link) {
+        let mut names = link.into_inner();
+        let computer1 = Computer::from_str(names.next().unwrap().as_str());
+        let computer2 = Computer::from_str(names.next().unwrap().as_str());
+        computers.insert(computer1);
+        computers.insert(computer2);
         links.push((computer1, computer2));
-        //links.push((computer2, computer1));
-        //links.get(&computer1).as_mut().unwrap().push(computer2);
-        //links.get(&computer2).unwrap().push(computer1);
     }
     Network { computers, links }
 }
 
+fn read_input(input:&str) -> Result<Network, InputError> {
+    Ok(build_file(parse_rule::<Day23Parser, _>(Rule::file, input)?))
+}
+
 type SetOfThree = (Computer, Computer, Computer);
 
 fn normalize_set_of_three(set:SetOfThree) -> SetOfThree {
@@ -154,8 +159,8 @@ td-yn"
 #[test]
 fn test_example1() {
     let input = input1();
-    let lines = input.split('\n');
-    let network = read_input(lines);
+    let network = read_input(input).unwrap();
+    assert_eq!(read_input("kh-tc\nqp-khx").err().unwrap().to_string(), "line 2, column 6: expected end of line");
     let mut computers:Vec<String> = network.computers.iter().map(|computer| computer.to_string()).collect();
     computers.sort();
     assert_eq!(computers, vec!["aq", "cg", "co", "de", "ka", "kh", "qp", "ta", "tb", "tc", "td", "ub", "vc", "wh", "wq", "yn" ]);
@@ -194,9 +199,11 @@ fn test_example1() {
 /// Puzzle
 //////////////////////////////////////////
 
+use crate::helper::{expect_input, read_file_to_string};
+
 pub fn puzzle() {
-    let lines = crate::helper::read_file("input/day23.txt");
-    let network = read_input(lines.iter().map(|line| line.as_str()));
+    let input = read_file_to_string("input/day23.txt");
+    let network = expect_input(read_input(&input), "input/day23.txt", &input);
     let sets = find_sets_of_three(&network);
 
     println!("Day 23: There are {} sets of three computers", sets.len());
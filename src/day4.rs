@@ -1,95 +1,62 @@
-struct Puzzle {
-    width:u32,
-    height:u32,
-    letters:Vec<Vec<char>>
-}
+use crate::maps::{Direction, Orientations, PixelMap, Position, Template};
 
-type Position = (/* x: */ i32,/* y: */ i32);
-type Direction = (/* deltax: */ i32,/* deltay: */ i32);
-
-fn change_position(current:Position, direction:Direction) -> Position {
-    (current.0 + direction.0, current.1 + direction.1)
-}
-
-fn all_directions() -> [Direction;8] {
-    [( 1, 0),( 1, 1),( 0, 1),(-1, 1),
-     (-1, 0),(-1,-1),( 0,-1),( 1,-1)]
+struct Puzzle {
+    letters:PixelMap<char>
 }
 
 impl Puzzle {
     fn create<'a>(lines:impl Iterator<Item=&'a str>) -> Puzzle {
-        let mut rows = Vec::new();
-        for line in lines {
-            let mut row = Vec::new();
-            for c in line.chars() {
-                row.push(c);
-            }
-            rows.push(row);
-        }
-        let height = rows.len() as u32;
-        let width = rows[0].len() as u32;
-        for row in &rows {
-            assert_eq!(row.len() as u32,width);
-        }
-        Puzzle { width, height, letters: rows }
+        Puzzle { letters:PixelMap::from_strings(lines) }
     }
 
     fn letter_at(&self, position:Position) -> char {
-        self.letters[position.1 as usize][position.0 as usize]
-    }
-
-    fn is_valid_position(&self, position:Position) -> bool {
-        position.0 >= 0 && position.0 < self.width as i32 &&
-        position.1 >= 0 && position.1 < self.height as i32
+        self.letters[position]
     }
 
     fn matches(&self, text:&str, position:Position, direction:Direction) -> bool {
-        let mut pos = position;
-        for c in text.chars() {
-            if !self.is_valid_position(pos) { return false; }
-            if c != self.letter_at(pos) { return false; }
-            pos = change_position(pos, direction);
-        }
-        return true;
+        let letters = self.letters.area.ray(position, direction).map(|pos| self.letter_at(pos));
+        letters.take(text.chars().count()).eq(text.chars())
     }
 
     fn find(&self, text:&str) -> u32 {
         let mut match_count = 0;
-        let all_directions = all_directions();
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let position = (x as i32,y as i32);
-                for direction in &all_directions {
-                    if self.matches(text, position, *direction) {
-                        match_count += 1;
-                    }
+        for position in self.letters.area.all_positions() {
+            for direction in Direction::eight_directions() {
+                if self.matches(text, position, direction) {
+                    match_count += 1;
                 }
             }
         }
         match_count
     }
 
+    // both diagonals through an 'A' read "MAS" or "SAM"
     fn find_xmas(&self) -> u32 {
-        let mut match_count = 0;
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let upper_left   = ((x  ) as i32,(y  ) as i32);
-                let upper_right  = ((x+2) as i32,(y  ) as i32);
-                let bottom_left  = ((x  ) as i32,(y+2) as i32);
-                let bottom_right = ((x+2) as i32,(y+2) as i32);
-
-                if ( self.matches("MAS", upper_left , (1, 1)) || self.matches("MAS", bottom_right, (-1,-1)) )
-                && ( self.matches("MAS", bottom_left, (1,-1)) || self.matches("MAS", upper_right,  (-1, 1)) ) {
-                    match_count += 1;
-                }
-            }
-        }
-        match_count
+        let xmas = Template::template_from_strings("M?S\n?A?\nM?S".split('\n'), '?');
+        self.letters.find_pattern(&xmas, Orientations::All).len() as u32
     }
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day4.pest"]
+struct Day4Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Puzzle, InputError> {
+    Ok(Puzzle::create(map_rows(file_rule, Rule::row)?.into_iter()))
+}
+
+fn read_puzzle(input:&str) -> Result<Puzzle, InputError> {
+    build_file(parse_rule::<Day4Parser, _>(Rule::file, input)?)
+}
+
 #[test]
 fn test_parsing() {
+    use crate::maps::Area;
     let input1 =
 "..X...
 .SAMX.
@@ -97,19 +64,24 @@ fn test_parsing() {
 XMAS.S
 .X....";
     let puzzle1 = Puzzle::create(input1.split("\n"));
-    assert_eq!(puzzle1.width, 6);
-    assert_eq!(puzzle1.height, 5);
-    assert_eq!(puzzle1.letters[1], vec!['.','S','A','M','X','.']);
+    assert_eq!(puzzle1.letters.area, Area::new(6, 5));
+    assert_eq!(puzzle1.letters.row(1), ['.','S','A','M','X','.']);
+
+    assert_eq!(read_puzzle(input1).unwrap().letters, puzzle1.letters);
+    assert_eq!(read_puzzle("XMAS\nXMAZ").err().unwrap().to_string(), "line 2, column 4: expected '.' or 'A' or 'M' or 'S' or 'X' or row");
 }
 
 #[test]
 fn test_move() {
-    let p = (2,3);
-    assert_eq!(change_position(p,(1,0)),(3,3));
+    use crate::maps::{Area, Point};
+    let area = Area::new(6, 5);
+    assert_eq!(area.step(Point(2,3),Direction::Right),Some(Point(3,3)));
+    assert_eq!(area.step(Point(2,4),Direction::DownLeft),None);
 }
 
 #[test]
 fn test_puzzle() {
+    use crate::maps::Point;
 
     let input0 =
 "M.S
@@ -125,9 +97,9 @@ M.S";
 XMAS.S
 .X....";
     let puzzle1 = Puzzle::create(input1.split('\n'));
-    assert_eq!(puzzle1.matches("XMAS",(0,3),(1,0)), true);
-    assert_eq!(puzzle1.matches("XMAS",(1,1),(1,0)), false);
-    assert_eq!(puzzle1.matches("XMAS",(4,1),(1,0)), false);
+    assert_eq!(puzzle1.matches("XMAS",Point(0,3),Direction::Right), true);
+    assert_eq!(puzzle1.matches("XMAS",Point(1,1),Direction::Right), false);
+    assert_eq!(puzzle1.matches("XMAS",Point(4,1),Direction::Right), false);
     assert_eq!(puzzle1.find("XMAS"), 4);
 
     let input2 =
@@ -148,17 +120,12 @@ MXMXAXMASX";
 
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 pub fn puzzle() {
-    let file = File::open("input/day4.txt").expect("Could not open input/day4.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
+    let input = read_file_to_string("input/day4.txt");
 
-    let puzzle = Puzzle::create(lines.iter().map( |line| line.as_str()));
+    let puzzle = expect_input(read_puzzle(&input), "input/day4.txt", &input);
     println!("Day 4, Part 1: There are {} findings of XMAS", puzzle.find("XMAS"));
     println!("Day 4, Part 2: There are {} findings of X-MAS", puzzle.find_xmas());
 
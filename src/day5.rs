@@ -1,5 +1,7 @@
 use std::cmp::Ordering;
 
+use crate::helper::{InputError, Sections};
+
 const VERBOSE:bool = false;
 
 type Page = u32;
@@ -108,33 +110,63 @@ fn test_puzzle_helper() {
 /// Parsing
 //////////////////////////////////////////
 
-fn read_puzzle(lines:Vec<String>) -> Puzzle {
-    // section 1 = rules
-    // section 2 = updates
-    let mut section = 1;
+// the generated pest Rule would clash with the page ordering Rule
+mod grammar {
+    use pest_derive::Parser;
+
+    #[derive(Parser)]
+    #[grammar = "../grammar/day5.pest"]
+    pub struct Day5Parser;
+}
+
+use pest::iterators::Pair;
+use grammar::{Day5Parser, Rule as GrammarRule};
+use crate::helper::parse::{parse_rule, value};
+
+fn build_pages(pair:Pair<'_, GrammarRule>) -> Result<Vec<Page>, InputError> {
+    pair.into_inner().map(|page| value::<Page>(page.as_str()).map_err(|err| err.at_pair(&page))).collect()
+}
+
+fn build_rules(rules_rule:Pair<'_, GrammarRule>) -> Result<Vec<Rule>, InputError> {
     let mut rules = Vec::new();
+    for pair in rules_rule.into_inner() {
+        match pair.as_rule() {
+            GrammarRule::rule => {
+                if VERBOSE { println!("{}", pair.as_str()); }
+                let pages = build_pages(pair)?;
+                rules.push((pages[0], pages[1]));
+            },
+            GrammarRule::EOI => {},
+            _ => unreachable!()
+        }
+    }
+    Ok(rules)
+}
+
+fn build_updates(updates_rule:Pair<'_, GrammarRule>) -> Result<Vec<Update>, InputError> {
     let mut updates = Vec::new();
-    for line in lines {
-        if VERBOSE { println!("{}", line); }
-        if line == "" {
-            section += 1;
-        } else if section == 1 {
-            let mut parts = line.split("|");
-            let a = parts.next().unwrap().parse::<Page>().unwrap();
-            let b = parts.next().unwrap().parse::<Page>().unwrap();
-            assert!(parts.next().is_none());
-            rules.push((a,b));
-        } else if section == 2 {
-            let parts = line.split(",");
-            let update = parts.map(|s| s.parse::<Page>().unwrap()).collect();
-            updates.push(update);
+    for pair in updates_rule.into_inner() {
+        match pair.as_rule() {
+            GrammarRule::update => {
+                if VERBOSE { println!("{}", pair.as_str()); }
+                updates.push(build_pages(pair)?);
+            },
+            GrammarRule::EOI => {},
+            _ => unreachable!()
         }
     }
-    Puzzle{rules:Rules{rules:rules}, updates}
+    Ok(updates)
+}
+
+fn read_puzzle<'a>(lines:impl Iterator<Item=&'a str>) -> Result<Puzzle, InputError> {
+    let sections = Sections::read(lines, &["rules", "updates"], &[])?;
+    let rules = sections.get("rules").parse(|text| build_rules(parse_rule::<Day5Parser, _>(GrammarRule::rules, text)?))?;
+    let updates = sections.get("updates").parse(|text| build_updates(parse_rule::<Day5Parser, _>(GrammarRule::updates, text)?))?;
+    Ok(Puzzle{rules:Rules{rules:rules}, updates})
 }
 
 #[cfg(test)]
-fn input1() -> Vec<String> {
+fn input1() -> &'static str {
 "47|53
 97|13
 97|61
@@ -162,12 +194,25 @@ fn input1() -> Vec<String> {
 75,29,13
 75,97,47,61,53
 61,13,29
-97,13,75,29,47".split("\n").map(|s| s.to_string()).collect()
+97,13,75,29,47"
+}
+
+#[test]
+fn test_read_puzzle_error() {
+    let input = "47|53\n97-13\n\n75,47,61";
+    let error = read_puzzle(input.split('\n')).err().unwrap();
+    assert_eq!(error.render(input),
+"line 2, column 3: expected '|' or page
+  |
+2 | 97-13
+  |   ^");
+    let error = read_puzzle("47|53\n\n75,47,\n".split('\n')).err().unwrap();
+    assert_eq!(error.to_string(), "line 3, column 7: expected page");
 }
 
 #[test]
 fn test_read_puzzle() {
-    let puzzle = read_puzzle(input1());
+    let puzzle = read_puzzle(input1().split('\n')).unwrap();
     assert_eq!(puzzle.rules.rules.len(), 21);
     assert_eq!(puzzle.rules.rules[0], (47,53));
     assert_eq!(puzzle.updates.len(), 6);
@@ -195,17 +240,12 @@ fn test_read_puzzle() {
 /// Real Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 pub fn puzzle() {
-    let file = File::open("input/day5.txt").expect("Could not open input/day5.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
+    let input = read_file_to_string("input/day5.txt");
 
-    let puzzle = read_puzzle(lines);
+    let puzzle = expect_input(read_puzzle(input.lines()), "input/day5.txt", &input);
     let sum1 = puzzle.sum_of_correct_middle_pages();
     println!("Day 3, Part 1: Sum of middlepages of correct updates is {}", sum1);
 
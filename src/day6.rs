@@ -1,28 +1,32 @@
 use std::collections::HashSet;
 
-use crate::maps::Position;
+use crate::maps::{Grid, PixelMap, Point, Position, SparseMap};
+use crate::render::{Color, Renderer, Style};
 
 use crate::maps::Direction;
 use crate::maps::Area;
 
+const VERBOSE:bool = false;
+const WRITE_ANIMATION:bool = false;
+
 struct Map {
     area:Area,
-    obstructions:HashSet<Position>,
+    obstructions:SparseMap<()>,
     start:Position // Direction is up
 }
 
-fn read_map(lines:&Vec<String>) -> Map {
-    let area = Area{width: lines[0].len(), height: lines.len()};
-    let mut obstructions:HashSet<Position> = HashSet::new();
-    let mut start:Position = (999,999);
+fn read_map(lines:&[&str]) -> Map {
+    let area = Area::new(lines[0].len(), lines.len());
+    let mut obstructions = SparseMap::new(area.clone());
+    let mut start:Position = Point(999,999);
     for y in 0..area.height {
         let chars = lines[y].chars();
         let mut x = 0;
         for c in chars {
             match c {
                 '.' => { },
-                '#' => { obstructions.insert((x,y)); },
-                '^' => { start = (x,y); },
+                '#' => { obstructions.set(Point(x,y), ()); },
+                '^' => { start = Point(x,y); },
                 _ => panic!("Unexpected character '{}'", c)
             }
             x += 1;
@@ -32,8 +36,25 @@ fn read_map(lines:&Vec<String>) -> Map {
 
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day6.pest"]
+struct Day6Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Map, InputError> {
+    Ok(read_map(&map_rows(file_rule, Rule::row)?))
+}
+
+fn read_input(input:&str) -> Result<Map, InputError> {
+    build_file(parse_rule::<Day6Parser, _>(Rule::file, input)?)
+}
+
 #[cfg(test)]
-fn input1() -> Vec<String> {
+fn input1() -> &'static str {
 "....#.....
 .........#
 ..........
@@ -43,83 +64,110 @@ fn input1() -> Vec<String> {
 .#..^.....
 ........#.
 #.........
-......#...".split('\n').map(|s| s.to_string()).collect()
+......#..."
 }
 
 #[test]
 fn test_map()
 {
-    let map = read_map(&input1());
+    let map = read_input(input1()).unwrap();
     assert_eq!(map.area.width, 10);
     assert_eq!(map.area.height, 10);
-    assert_eq!(map.start, (4,6));
+    assert_eq!(map.start, Point(4,6));
     assert_eq!(map.obstructions.len(), 8);
-    assert!(map.obstructions.contains(&(2,3)));
-    assert_eq!(map.area.step((3,4),Direction::Up),Some((3,3)));
-    assert_eq!(map.area.step((3,0),Direction::Up),None);
+    assert_eq!(map.obstructions.get(Point(2,3)), Some(()));
+    assert_eq!(map.obstructions.get(Point(3,2)), None);
+    assert_eq!(map.area.step(Point(3,4),Direction::Up),Some(Point(3,3)));
+    assert_eq!(map.area.step(Point(3,0),Direction::Up),None);
+
+    assert_eq!(read_input("..#.\n.^\n").err().unwrap().to_string(), "line 2, column 3: row has 2 characters instead of 4");
+}
+
+use crate::trace::{Event, Recorder};
+use crate::image::{create_animation, GifWriter};
+use crate::maps::ToChar;
+
+// the obstructions as '#' and free positions as '.'
+fn map_image(map:&Map) -> PixelMap<char> {
+    PixelMap::from_fn(map.area.width, map.area.height, |pos| if map.obstructions.get(pos).is_some() { '#' } else { '.' })
+}
+
+// the route so far with 'X' and the guard on top
+fn add_walk_frame(animation:&mut GifWriter<impl std::io::Write>, image:&mut PixelMap<char>, pos:Position, direction:Direction) {
+    image.set_at(pos, direction.to_char());
+    animation.add_frame(image).expect("Could not write animation");
+    image.set_at(pos, 'X');
 }
 
-fn walk(map:&Map) -> HashSet<Position> {
+fn walk(map:&Map, recorder:&mut Recorder) -> HashSet<Position> {
     let mut pos = map.start;
     let mut direction = Direction::Up;
-    let mut positions:HashSet<Position> = HashSet::new();
+    let mut positions:HashSet<Position> = HashSet::from([pos]);
+    let mut step = 0;
+    let mut animation = if WRITE_ANIMATION { Some((create_animation("day6", 2, 4).expect("Could not create animation"), map_image(map))) } else { None };
+    if recorder.enabled() { recorder.record(Event::new(6, step).position(pos).direction(direction)); }
+    if let Some((animation, image)) = &mut animation { add_walk_frame(animation, image, pos, direction); }
     loop {
-        positions.insert(pos);
-        let new_poso = map.area.step(pos, direction);
-        if new_poso.is_none() {
-            return positions;
-        }
-        let new_pos = new_poso.unwrap();
-        if map.obstructions.contains(&new_pos) {
-            direction = direction.turn_right();
+        // walk straight up to the next obstruction or the edge, the ray starts at the current position
+        for next_pos in map.area.ray(pos, direction).skip(1).take_while(|&p| map.obstructions.get(p).is_none()) {
+            step += 1;
+            if recorder.enabled() { recorder.record(Event::new(6, step).position(next_pos).direction(direction)); }
+            if let Some((animation, image)) = &mut animation { add_walk_frame(animation, image, next_pos, direction); }
+            positions.insert(next_pos);
+            pos = next_pos;
         }
-        else
-        {
-            pos = new_pos;
+        if map.area.step(pos, direction).is_none() {
+            if let Some((animation, _)) = animation { animation.finish().expect("Could not write animation"); }
+            return positions;
         }
+        direction = direction.turn_right();
     }
 }
 
 fn walk_is_loop(map:&Map, additional_obstruction:Position) -> bool {
     let mut pos = map.start;
     let mut direction = Direction::Up;
-    let mut posdirs:HashSet<(Position,Direction)> = HashSet::new();
+    // it is enough to remember where the guard turned
+    let mut turns:HashSet<(Position,Direction)> = HashSet::new();
     loop {
-        if posdirs.contains(&(pos,direction)) {
-            // Loop detected!
-            return true;
-        }
-        posdirs.insert((pos,direction));
-        let new_poso = map.area.step(pos, direction);
-        if new_poso.is_none() {
+        let is_free = |p:&Position| map.obstructions.get(*p).is_none() && *p != additional_obstruction;
+        pos = map.area.ray(pos, direction).skip(1).take_while(is_free).last().unwrap_or(pos);
+        if map.area.step(pos, direction).is_none() {
             return false;
         }
-        let new_pos = new_poso.unwrap();
-        if map.obstructions.contains(&new_pos) || new_pos == additional_obstruction {
-            direction = direction.turn_right();
-        }
-        else
-        {
-            pos = new_pos;
+        if !turns.insert((pos,direction)) {
+            // Loop detected!
+            return true;
         }
+        direction = direction.turn_right();
     }
 }
 
 #[test]
 fn test_walk() {
-    let map = read_map(&input1());
-    let positions = walk(&map);
-    assert!(positions.contains(&(2,4)));
+    let map = read_input(input1()).unwrap();
+    let positions = walk(&map, &mut Recorder::disabled());
+    assert!(positions.contains(&Point(2,4)));
     assert_eq!(positions.len(), 41);
-    assert_eq!(walk_is_loop(&map, (1,1)),false);
-    assert_eq!(walk_is_loop(&map, (3,6)),true);
+
+    // one event for the start and one per step, none for the turns
+    let mut trace = Vec::new();
+    walk(&map, &mut Recorder::new(&mut trace));
+    let trace = String::from_utf8(trace).unwrap();
+    let events:Vec<serde_json::Value> = trace.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(events[0]["position"], serde_json::json!([4, 6]));
+    assert!(events.windows(2).all(|pair| pair[0]["position"] != pair[1]["position"]));
+    assert_eq!(events.len(), 44 + 1);
+
+    assert_eq!(walk_is_loop(&map, Point(1,1)),false);
+    assert_eq!(walk_is_loop(&map, Point(3,6)),true);
     assert_eq!(count_obstructions_that_loop(&map), 6);
 }
 
 fn count_obstructions_that_loop(map:&Map) -> u32 {
     let mut counter = 0;
     // we only need to check positions where the guard would walk to
-    let positions = walk(&map);
+    let positions = walk(&map, &mut Recorder::disabled());
     for position in positions {
         if walk_is_loop(map, position) {
             counter += 1;
@@ -128,27 +176,32 @@ fn count_obstructions_that_loop(map:&Map) -> u32 {
     counter
 }
 
+// the route is marked with 'X' like in the puzzle description
+fn print_route(map:&Map, positions:&HashSet<Position>) {
+    let image = map_image(map);
+    Renderer::new(&image)
+        .highlight(positions.iter().copied(), Style::foreground(Color::Red).with_character('X'))
+        .highlight([map.start], Style::foreground(Color::Yellow).with_character('^'))
+        .println();
+}
+
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
 use std::time::Instant;
+use crate::helper::{expect_input, read_file_to_string};
 
 
-pub fn puzzle() {
-    let file = File::open("input/day6.txt").expect("Could not open input/day6.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
+pub fn puzzle(recorder:&mut Recorder) {
+    let input = read_file_to_string("input/day6.txt");
 
-    let map = read_map(&lines);
+    let map = expect_input(read_input(&input), "input/day6.txt", &input);
 
     let start1 = Instant::now();
-    let positions = walk(&map);
+    let positions = walk(&map, recorder);
     println!("Day 6, Part 1: Guard was on {} unique positions ({} milliseconds)", positions.len(), start1.elapsed().as_millis());
+    if VERBOSE { print_route(&map, &positions); }
 
     let start2 = Instant::now();
     println!("Day 6, Part 2: There are {} positions for another obstruction that loop ({} seconds)", count_obstructions_that_loop(&map), start2.elapsed().as_secs());
@@ -1,3 +1,6 @@
+use crate::helper::InputError;
+use crate::helper::parse::{fields, list, parse_lines, value, ParseError};
+
 type Value = u64;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -17,21 +20,17 @@ struct Equation {
 
 type Equations = Vec<Equation>;
 
-fn parse_equation(line:&str) -> Equation {
-    let mut split_colon = line.split(": ");
-    let result = split_colon.next().unwrap().parse::<Value>().unwrap();
-    let operands_str = split_colon.next().unwrap();
-    assert_eq!(split_colon.next(), None);
-    let mut operands = Vec::new();
-    let mut operators = Vec::new();
-    let split_space = operands_str.split(' ');
-    for operand_str in split_space {
-        operands.push(operand_str.parse::<Value>().unwrap());
-    }
-    for _operator_index in 0..operands.len()-1 {
-        operators.push(Operator::UNKNOWN);
-    }
-    Equation { result, operands, operators }
+// "3267: 81 40 27"
+fn parse_equation(line:&str) -> Result<Equation, ParseError> {
+    let [result_str, operands_str] = fields::<2>(line, ": ")?;
+    let result = value::<Value>(result_str)?;
+    let operands = list::<Value>(operands_str, " ").map_err(|err| err.shifted(result_str.len() + 2))?;
+    let operators = vec![Operator::UNKNOWN; operands.len() - 1];
+    Ok(Equation { result, operands, operators })
+}
+
+fn parse_equations(input:&str) -> Result<Equations, InputError> {
+    parse_lines(input, parse_equation)
 }
 
 fn concat(a:Value, b:Value) -> Value {
@@ -82,7 +81,7 @@ fn equation_can_be_made_true(eq:&Equation, possible_operators:&Vec<Operator>) ->
 
 #[test]
 fn test_equation() {
-    let eq = parse_equation("3267: 81 40 27");
+    let eq = parse_equation("3267: 81 40 27").unwrap();
     assert_eq!(eq.result, 3267);
     assert_eq!(eq.operands, vec![81,40,27]);
     assert_eq!(eq.operators, vec![Operator::UNKNOWN, Operator::UNKNOWN]);
@@ -103,17 +102,17 @@ fn test_equation() {
     let part1 = vec![Operator::PLUS, Operator::MULTIPLY];
     assert_eq!(equation_can_be_made_true(&eq, &part1), true);
 
-    let eq5 = parse_equation("161011: 16 10 13");
+    let eq5 = parse_equation("161011: 16 10 13").unwrap();
     assert_eq!(equation_can_be_made_true(&eq5, &part1), false);
 
     let part2 = vec![Operator::PLUS, Operator::MULTIPLY, Operator::CONCAT];
-    let eq6 = parse_equation("156: 15 6");
+    let eq6 = parse_equation("156: 15 6").unwrap();
     assert_eq!(equation_can_be_made_true(&eq6, &part1), false);
     assert_eq!(equation_can_be_made_true(&eq6, &part2), true);
-    let eq7 = parse_equation("7290: 6 8 6 15");
+    let eq7 = parse_equation("7290: 6 8 6 15").unwrap();
     assert_eq!(equation_can_be_made_true(&eq7, &part1), false);
     assert_eq!(equation_can_be_made_true(&eq7, &part2), true);
-    let eq8 = parse_equation("192: 17 8 14");
+    let eq8 = parse_equation("192: 17 8 14").unwrap();
     assert_eq!(equation_can_be_made_true(&eq8, &part2), true);
 
 }
@@ -138,15 +137,16 @@ fn test_equations() {
 192: 17 8 14
 21037: 9 7 18 13
 292: 11 6 16 20";
-    let eqs:Equations = input.split('\n').map(|line| parse_equation(line)).collect();
+    let eqs:Equations = parse_equations(input).unwrap();
     assert_eq!(eqs.len(), 9);
+    assert_eq!(parse_equations("190: 10 19\n83: 17 99999999999999999999").err().unwrap().to_string(), "line 2, column 8: '99999999999999999999' is no u64");
 
     let part1 = vec![Operator::PLUS, Operator::MULTIPLY];
     assert_eq!(equation_can_be_made_true(&eqs[0], &part1), true);
     assert_eq!(equation_can_be_made_true(&eqs[1], &part1), true);
     assert_eq!(equation_can_be_made_true(&eqs[2], &part1), false);
 
-    assert_eq!(eqs[1], parse_equation("3267: 81 40 27"));
+    assert_eq!(eqs[1], parse_equation("3267: 81 40 27").unwrap());
     assert_eq!(count_equations_that_can_be_made_true(&eqs, &part1), 3);
     assert_eq!(sum_equations_that_can_be_made_true(&eqs, &part1), 3749);
 
@@ -159,18 +159,13 @@ fn test_equations() {
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 
 pub fn puzzle() {
-    let file = File::open("input/day7.txt").expect("Could not open input/day7.txt");
-    let reader = BufReader::new(file);
-
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
+    let input = read_file_to_string("input/day7.txt");
 
-    let eqs:Equations = lines.iter().map(|line| parse_equation(line)).collect();
+    let eqs:Equations = expect_input(parse_equations(&input), "input/day7.txt", &input);
 
     let part1 = vec![Operator::PLUS, Operator::MULTIPLY];
     let count1 = count_equations_that_can_be_made_true(&eqs, &part1);
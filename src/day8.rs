@@ -1,44 +1,41 @@
 use std::collections::HashSet;
 use std::ops::Range;
 
-use crate::maps::Position;
+use crate::maps::{Grid, Point, Position, SparseMap, Svg};
 use crate::maps::Area;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-struct Antenna {
-    frequency:char,
-    position:Position
-}
+// the antennas and antinodes are drawn to frames/
+const WRITE_SVG:bool = false;
 
 struct Map {
     area:Area,
-    antennas:Vec<Antenna>
+    antennas:SparseMap<char> // the frequency at the position
 }
 
 impl Map {
     // mirror a at b with the given factors
     // only return if contained in map
-    fn mirror(&self, a:Position,b:Position, factors:Range<i32>) -> Vec<Position> {
+    fn mirror(&self, a:Position,b:Position, factors:Range<i64>) -> Vec<Position> {
         let mut mirrored = Vec::new();
+        let a = a.cast::<i64>().unwrap();
+        let b = b.cast::<i64>().unwrap();
         for fac in factors {
-            let x = b.0 as i32 + fac * (b.0 as i32 - a.0 as i32);
-            let y = b.1 as i32 + fac * (b.1 as i32 - a.1 as i32);
-            if self.area.contains_signed(x,y) {
-                mirrored.push((x as usize,y as usize));
+            if let Some(position) = self.area.to_position(b + (b - a) * fac) {
+                mirrored.push(position);
             }
         }
         mirrored
     }
 }
 
-fn parse_map(lines:&Vec<String>) -> Map {
-    let mut antennas:Vec<Antenna> = Vec::new();
-    let area = Area{width:lines[0].len(), height: lines.len()};
+fn parse_map(lines:&[&str]) -> Map {
+    let area = Area::new(lines[0].len(), lines.len());
+    let mut antennas = SparseMap::new(area.clone());
     for y in 0..area.height {
         let mut x = 0;
         for c in lines[y as usize].chars() {
             if c != '.' {
-                antennas.push(Antenna{frequency:c, position:(x,y)});
+                antennas.set(Point(x,y), c);
             }
             x += 1;
         }
@@ -46,8 +43,25 @@ fn parse_map(lines:&Vec<String>) -> Map {
     Map { area, antennas }
 }
 
+use pest_derive::Parser;
+use pest::iterators::Pair;
+use crate::helper::InputError;
+use crate::helper::parse::{map_rows, parse_rule};
+
+#[derive(Parser)]
+#[grammar = "../grammar/day8.pest"]
+struct Day8Parser;
+
+fn build_file(file_rule:Pair<'_, Rule>) -> Result<Map, InputError> {
+    Ok(parse_map(&map_rows(file_rule, Rule::row)?))
+}
+
+fn read_input(input:&str) -> Result<Map, InputError> {
+    build_file(parse_rule::<Day8Parser, _>(Rule::file, input)?)
+}
+
 #[cfg(test)]
-fn input1() -> Vec<String> {
+fn input1() -> &'static str {
 "............
 ........0...
 .....0......
@@ -59,74 +73,110 @@ fn input1() -> Vec<String> {
 ........A...
 .........A..
 ............
-............".split('\n').map(|line| line.to_string()).collect()
+............"
 }
 
 #[test]
 fn test_parse() {
-    let map = parse_map(&input1());
+    let map = read_input(input1()).unwrap();
     assert_eq!(map.area.width, 12);
     assert_eq!(map.area.height, 12);
     assert_eq!(map.antennas.len(), 7);
-    assert_eq!(map.antennas[4], Antenna{frequency:'A', position:(6,5)});
+    assert_eq!(map.antennas.get(Point(6,5)), Some('A'));
+    assert_eq!(map.antennas.get(Point(5,6)), None);
+
+    assert_eq!(read_input("..0.\n.#..").err().unwrap().to_string(), "line 2, column 2: expected '.' or row");
 }
 
-fn determine_antinodes(map:&Map, factors:Range<i32>) -> HashSet<Position> {
-    let mut antinodes = HashSet::new();
-    let len = map.antennas.len();
+// all pairs of antennas with the same frequency, in reading order
+fn antenna_pairs(map:&Map) -> Vec<(Position, Position)> {
+    let mut pairs = Vec::new();
+    let mut antennas:Vec<(Position, char)> = map.antennas.occupied().collect();
+    antennas.sort_by_key(|(pos, _)| (pos.1, pos.0));
+    let len = antennas.len();
     for i in 0..len {
-        let a = map.antennas[i];
-        for j in i+1..len {
-            let b = map.antennas[j];
-            if a.frequency == b.frequency {
-                for antinode in map.mirror(a.position,b.position,factors.clone()) {
-                    antinodes.insert(antinode);
-                };
-                for antinode in map.mirror(b.position,a.position,factors.clone()) {
-                    antinodes.insert(antinode);
-                };
+        let (a, frequency_a) = antennas[i];
+        for &(b, frequency_b) in &antennas[i+1..] {
+            if frequency_a == frequency_b {
+                pairs.push((a, b));
             }
-
         }
     }
+    pairs
+}
+
+fn determine_antinodes(map:&Map, factors:Range<i64>) -> HashSet<Position> {
+    let mut antinodes = HashSet::new();
+    for (a, b) in antenna_pairs(map) {
+        for antinode in map.mirror(a,b,factors.clone()) {
+            antinodes.insert(antinode);
+        };
+        for antinode in map.mirror(b,a,factors.clone()) {
+            antinodes.insert(antinode);
+        };
+    }
     antinodes
 }
 
+// a line through every pair up to its outermost antinodes, the antennas are labelled with their frequency
+fn draw_antinodes(map:&Map, factors:Range<i64>) -> Svg {
+    let mut svg = Svg::new(&map.area, 12);
+    for (a, b) in antenna_pairs(map) {
+        let from = map.mirror(b,a,factors.clone()).last().copied().unwrap_or(a);
+        let to = map.mirror(a,b,factors.clone()).last().copied().unwrap_or(b);
+        svg = svg.line(from, to, "gray");
+    }
+    for antinode in determine_antinodes(map, factors) {
+        svg = svg.point(antinode, "", "red");
+    }
+    for (antenna, frequency) in map.antennas.occupied() {
+        svg = svg.point(antenna, &frequency.to_string(), "blue");
+    }
+    svg
+}
+
 #[test]
 fn test_determine_antinodes() {
-    let map = parse_map(&input1());
+    let map = read_input(input1()).unwrap();
     let factors1 = 1..2;
     let antinodes1 = determine_antinodes(&map,factors1);
-    assert!(antinodes1.contains(&(3,1)));
-    assert!(!antinodes1.contains(&(10,9)));
-    assert!(antinodes1.contains(&(10,10)));
-    assert!(antinodes1.contains(&(10,11)));
+    assert!(antinodes1.contains(&Point(3,1)));
+    assert!(!antinodes1.contains(&Point(10,9)));
+    assert!(antinodes1.contains(&Point(10,10)));
+    assert!(antinodes1.contains(&Point(10,11)));
     assert_eq!(antinodes1.len(), 14);
     let factors2 = 0..100;
     let antinodes1 = determine_antinodes(&map,factors2);
-    assert!(antinodes1.contains(&(10,10)));
-    assert!(!antinodes1.contains(&(11,10)));
-    assert!(antinodes1.contains(&(10,11)));
-    assert!(antinodes1.contains(&(11,11)));
+    assert!(antinodes1.contains(&Point(10,10)));
+    assert!(!antinodes1.contains(&Point(11,10)));
+    assert!(antinodes1.contains(&Point(10,11)));
+    assert!(antinodes1.contains(&Point(11,11)));
     assert_eq!(antinodes1.len(), 34);
 }
 
+#[test]
+fn test_draw_antinodes() {
+    let map = read_input(input1()).unwrap();
+    assert_eq!(antenna_pairs(&map).len(), 6 + 3);
+    let svg = draw_antinodes(&map, 1..2).to_string();
+    assert_eq!(svg.matches("stroke=\"gray\"").count(), 9);
+    assert_eq!(svg.matches("fill=\"red\"").count(), 14);
+    assert_eq!(svg.matches("</text>").count(), 7);
+    // the antinodes of the 'A' pair at (8,8) and (9,9) are at (7,7) and (10,10)
+    assert!(svg.contains("<line x1=\"90\" y1=\"90\" x2=\"126\" y2=\"126\" stroke=\"gray\"/>"));
+}
+
 //////////////////////////////////////////
 /// Puzzle
 //////////////////////////////////////////
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
+use crate::helper::{expect_input, read_file_to_string};
 
 
 pub fn puzzle() {
-    let file = File::open("input/day8.txt").expect("Could not open input/day8.txt");
-    let reader = BufReader::new(file);
+    let input = read_file_to_string("input/day8.txt");
 
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
-
-    let map = parse_map(&lines);
+    let map = expect_input(read_input(&input), "input/day8.txt", &input);
     let factors1 = 1..2;
     let antinodes1 = determine_antinodes(&map, factors1);
     println!("Day 8, Part 1: Map contains {} antennas and {} antinodes", map.antennas.len(), antinodes1.len());
@@ -135,4 +185,9 @@ pub fn puzzle() {
     let antinodes2 = determine_antinodes(&map, factors2);
     println!("Day 8, Part 2: Map contains {} antennas and {} antinodes", map.antennas.len(), antinodes2.len());
 
+    if WRITE_SVG {
+        draw_antinodes(&map, 1..2).save("day8_part1").expect("Could not write drawing");
+        draw_antinodes(&map, 0..100).save("day8_part2").expect("Could not write drawing");
+    }
+
 }
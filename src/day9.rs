@@ -39,19 +39,36 @@ impl Disk {
 
 #[test]
 fn test_find_free_block() {
-    let disk = read_input("34567");
+    let disk = read_disk("34567".as_bytes()).unwrap();
     assert_eq!(disk.find_free_block(4),Some(3));
     assert_eq!(disk.find_free_block(5),Some(3+4+5));
     assert_eq!(disk.find_free_block(6),Some(3+4+5));
     assert_eq!(disk.find_free_block(7),None);
 }
 
-fn read_input(line:&str) -> Disk {
+use std::io::BufRead;
+use crate::helper::InputError;
+
+// every character of the disk map is one block size
+// the disk map is the first line, column starts with 1
+fn block_size(column:usize, byte:u8) -> Result<u32, InputError> {
+    if byte.is_ascii_digit() {
+        Ok((byte - b'0') as u32)
+    } else {
+        Err(InputError { line:1, column:Some(column), length:1, message:"expected a block size digit".to_string() })
+    }
+}
+
+// the size of a file, then the size of the free space behind it, and so on
+// the sizes are consumed one by one, so the disk map is never held as a whole
+fn build_disk_map(block_sizes:impl Iterator<Item=Result<u32, InputError>>) -> Result<Disk, InputError> {
     let mut sectors:Vec<Option<Fileid>> = Vec::new();
     let mut is_file_next = true;
     let mut fileid = 0;
-    for c in line.chars() {
-        let blocksize = c.to_digit(10).unwrap();
+    let mut count = 0;
+    for blocksize in block_sizes {
+        let blocksize = blocksize?;
+        count += 1;
         for _i in 0..blocksize {
             if is_file_next {
                 sectors.push(Some(fileid));
@@ -70,7 +87,41 @@ fn read_input(line:&str) -> Disk {
             fileid += 1;
         }
     }
-    Disk { sectors }
+    if count == 0 {
+        return Err(InputError { line:1, column:Some(1), length:1, message:"expected a block size digit".to_string() });
+    }
+    Ok(Disk { sectors })
+}
+
+// the disk map of the real input has hundreds of MB, so it is parsed while reading
+// only empty lines may follow it
+fn read_disk(reader:impl BufRead) -> Result<Disk, InputError> {
+    let mut bytes = reader.bytes()
+        .map(|byte| byte.unwrap_or_else(|err| panic!("Could not read the disk map: {}", err)))
+        .peekable();
+    let mut column = 0;
+    let block_sizes = std::iter::from_fn(|| {
+        let byte = bytes.next_if(|&byte| byte != b'\n')?;
+        column += 1;
+        if byte == b'\r' && bytes.peek().is_none_or(|&next| next == b'\n') {
+            return None;
+        }
+        Some(block_size(column, byte))
+    });
+    let disk = build_disk_map(block_sizes)?;
+    let mut line = 1;
+    let mut column = 0;
+    for byte in bytes {
+        match byte {
+            b'\n' => { line += 1; column = 0; }
+            b'\r' => column += 1,
+            _ => {
+                column += 1;
+                return Err(InputError { line, column:Some(column), length:1, message:"unexpected line after the disk map".to_string() });
+            }
+        }
+    }
+    Ok(disk)
 }
 
 fn defrag1(before:&Disk) -> Disk {
@@ -120,8 +171,13 @@ fn defrag2(before:&Disk) -> Disk {
 
 #[test]
 fn test_read_input() {
-    let disk1 = read_input("12345");
+    let disk1 = read_disk("12345".as_bytes()).unwrap();
     assert_eq!(disk1.sectors.len(), 1+2+3+4+5);
+    assert_eq!(read_disk("123x5".as_bytes()).err().unwrap().to_string(), "line 1, column 4: expected a block size digit");
+    assert_eq!(read_disk("".as_bytes()).err().unwrap().to_string(), "line 1, column 1: expected a block size digit");
+    assert_eq!(read_disk("12345\r\n\r\n\n".as_bytes()).unwrap(), disk1);
+    assert_eq!(read_disk("12\r45".as_bytes()).err().unwrap().to_string(), "line 1, column 3: expected a block size digit");
+    assert_eq!(read_disk("12345\n\n 6".as_bytes()).err().unwrap().to_string(), "line 3, column 1: unexpected line after the disk map");
     assert_eq!(disk1.sectors,vec![
         Some(0),
         None, None,
@@ -141,7 +197,7 @@ fn test_read_input() {
     assert_eq!(disk1defrag.checksum(), 0*0+1*2+2*2+3*1+4*1+5*1+6*2+7*2+8*2);
 
 
-    let disk2 = read_input("2333133121414131402");
+    let disk2 = read_disk("2333133121414131402".as_bytes()).unwrap();
     assert_eq!(disk2.sectors, vec![
         Some(0), Some(0), None, None, None, Some(1), Some(1), Some(1), None, None, None, Some(2),
         None, None, None, Some(3), Some(3), Some(3), None, Some(4), Some(4), None, Some(5), Some(5), Some(5), Some(5),
@@ -178,17 +234,14 @@ fn test_read_input() {
 //////////////////////////////////////////
 
 use std::fs::File;
-use std::io::BufRead;
 use std::io::BufReader;
 use std::time::Instant;
 
 
 pub fn puzzle() {
     let file = File::open("input/day9.txt").expect("Could not open input/day9.txt");
-    let reader = BufReader::new(file);
 
-    let line:String = reader.lines().next().unwrap().unwrap();
-    let disk = read_input(line.as_str());
+    let disk = read_disk(BufReader::new(file)).unwrap_or_else(|err| panic!("Could not read input/day9.txt, {}", err));
 
     let start1 = Instant::now();
     let disk_defrag1 = defrag1(&disk);
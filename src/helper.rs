@@ -1,60 +1,407 @@
 // Some helper functions
 
-// split a string at empty lines
-// often used to seperate parts of the input for advent of code puzzles
+pub mod parse;
+
+//////////////////////////////////////////
+/// Input errors
+//////////////////////////////////////////
+
+// something is wrong with the input
+// line and column numbers start with 1
+#[derive(Debug, PartialEq)]
+pub struct InputError {
+    pub line:usize,
+    pub column:Option<usize>,
+    // number of characters with the problem, starting at column
+    pub length:usize,
+    pub message:String
+}
+
+impl InputError {
+    pub fn new(line:usize, message:String) -> Self {
+        InputError { line, column:None, length:1, message }
+    }
+
+    // the line numbers were counted in a part of the input which starts at first_line
+    pub fn after_line(self, first_line:usize) -> Self {
+        InputError { line:self.line + first_line - 1, ..self }
+    }
+
+    // show the line of the input with a caret under the problem
+    pub fn render(&self, input:&str) -> String {
+        self.render_line(input.lines().nth(self.line - 1).unwrap_or(""))
+    }
+
+    // like render() but with the line already extracted from the input
+    pub fn render_line(&self, line:&str) -> String {
+        let number = self.line.to_string();
+        let margin = " ".repeat(number.len());
+        let mut text = format!("{}\n{} |\n{} | {}", self, margin, number, line.trim_end_matches('\r'));
+        if let Some(column) = self.column {
+            text += &format!("\n{} | {}{}", margin, " ".repeat(column - 1), "^".repeat(self.length.max(1)));
+        }
+        text
+    }
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "line {}, column {}: {}", self.line, column, self.message),
+            None         => write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+}
+
+use pest::error::LineColLocation;
+
+impl<R:pest::RuleType> From<pest::error::Error<R>> for InputError {
+    fn from(error:pest::error::Error<R>) -> Self {
+        let message = error.variant.message().to_string();
+        match error.line_col {
+            LineColLocation::Pos((line, column)) =>
+                InputError { line, column:Some(column), length:1, message },
+            LineColLocation::Span((line, column), (end_line, end_column)) =>
+                InputError { line, column:Some(column), length:if end_line == line { end_column - column } else { 1 }, message }
+        }
+    }
+}
+
+impl InputError {
+    // pest::set_error_detail(true) records what was expected at the furthest position,
+    // which is closer to the problem than the start of the failed rule
+    // top_rule is the rule given to the parser, it only fails directly at EOI or NEWLINE
+    pub fn from_pest<R:pest::RuleType>(error:pest::error::Error<R>, input:&str, top_rule:R) -> Self {
+        let Some(attempts) = error.parse_attempts() else { return error.into() };
+        let Some(position) = pest::Position::new(input, attempts.max_position) else { return error.into() };
+        // only literal strings, the type of the tokens is not exported by pest
+        // ranges like '0..9' and builtins like ASCII_DIGIT are covered by the rule names
+        // a NEWLINE is possible almost everywhere
+        let mut expected:Vec<String> = attempts.expected_tokens().iter()
+            .map(|token| token.to_string())
+            .filter(|token| token != "BUILTIN_RULE" && !token.contains("..") && !token.trim_end_matches(['\r', '\n']).is_empty())
+            .map(|token| format!("'{}'", token))
+            .collect();
+        for call_stack in &attempts.call_stacks {
+            if let Some(&rule) = call_stack.deepest.get_rule() {
+                let name = if rule == top_rule { "end of line".to_string() } else { format!("{:?}", rule) };
+                if !expected.contains(&name) { expected.push(name); }
+            }
+        }
+        if expected.is_empty() {
+            return error.into();
+        }
+        let (line, column) = position.line_col();
+        InputError { line, column:Some(column), length:1, message:format!("expected {}", expected.join(" or ")) }
+    }
+}
+
+// return the parsed input or panic with the rendered error
+pub fn expect_input<T>(result:Result<T, InputError>, filename:&str, input:&str) -> T {
+    result.unwrap_or_else(|err| panic!("Could not read {}, {}", filename, err.render(input)))
+}
+
+// a small grammar for testing the helpers
 #[cfg(test)]
-pub fn split_input_sections<const SECTION_COUNT:usize>(input:&str) -> [String;SECTION_COUNT] {
-    let mut parts = input.split("\n\n");
-    const EMPTY_STRING: std::string::String = String::new();
-    let mut sections:[String;SECTION_COUNT] = [EMPTY_STRING;SECTION_COUNT];
-    for i in 0..SECTION_COUNT {
-        let part = parts.next().unwrap();
-        sections[i] = String::from(part);
+mod test_grammar {
+    use pest_derive::Parser;
+
+    #[derive(Parser)]
+    #[grammar_inline = "number = { ASCII_DIGIT+ }\nfile = { SOI ~ number ~ (NEWLINE ~ number)* ~ EOI }"]
+    pub struct TestParser;
+}
+
+#[test]
+fn test_render_error() {
+    use pest::Parser;
+    use test_grammar::{Rule, TestParser};
+
+    let input = "12\n345\n6x7";
+    let error = InputError::from(TestParser::parse(Rule::file, input).unwrap_err());
+    assert_eq!(error.line, 3);
+    assert_eq!(error.column, Some(2));
+    assert_eq!(error.render(input),
+"line 3, column 2: expected EOI
+  |
+3 | 6x7
+  |  ^");
+
+    let error = InputError { line:12, column:Some(3), length:3, message:"'999' is too big".to_string() };
+    assert_eq!(error.render_line("8,999,1"),
+"line 12, column 3: '999' is too big
+   |
+12 | 8,999,1
+   |   ^^^");
+
+    assert_eq!(InputError::new(2, "missing section".to_string()).after_line(5).render("a\nb\nc\nd\ne\nf"),
+"line 6: missing section
+  |
+6 | f");
+}
+
+//////////////////////////////////////////
+/// Sections
+//////////////////////////////////////////
+
+// a part of the input, separated from the other parts by an empty line
+#[derive(Debug, PartialEq)]
+pub struct Section<'a> {
+    pub name:&'static str,
+    // line number of lines[0]
+    pub first_line:usize,
+    pub lines:Vec<&'a str>
+}
+
+impl<'a> Section<'a> {
+    // error for lines[index]
+    pub fn error(&self, index:usize, message:String) -> InputError {
+        InputError::new(self.first_line + index, message)
+    }
+
+    pub fn expect_line_count(&self, count:usize) -> Result<(), InputError> {
+        if self.lines.len() == count {
+            Ok(())
+        } else {
+            Err(self.error(0, format!("section '{}' has {} lines instead of {}", self.name, self.lines.len(), count)))
+        }
+    }
+
+    // parse the lines of this section as one text
+    // line numbers in errors refer to the whole input
+    pub fn parse<T>(&self, parse:impl FnOnce(&str) -> Result<T, InputError>) -> Result<T, InputError> {
+        parse(&self.lines.join("\n")).map_err(|err| err.after_line(self.first_line))
+    }
+}
+
+// split the input at empty lines into named sections
+// often used to seperate parts of the input for advent of code puzzles
+// - runs of empty lines count as one separator, empty lines at begin and end are ignored
+// - carriage returns at line end are ignored
+// - optional sections may be missing at the end of the input
+#[derive(Debug)]
+pub struct Sections<'a> {
+    sections:Vec<Section<'a>>
+}
+
+impl<'a> Sections<'a> {
+    pub fn read(lines:impl Iterator<Item=&'a str>, required:&[&'static str], optional:&[&'static str]) -> Result<Sections<'a>, InputError> {
+        let names = || required.iter().chain(optional.iter());
+        let mut sections:Vec<Section<'a>> = Vec::new();
+        let mut in_section = false;
+        let mut line_count = 0;
+        for (index, line) in lines.enumerate() {
+            let line_number = index + 1;
+            line_count = line_number;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                in_section = false;
+                continue;
+            }
+            if !in_section {
+                let Some(&name) = names().nth(sections.len()) else {
+                    return Err(InputError::new(line_number, format!("unexpected section, expected at most {} sections ({})", names().count(), names().copied().collect::<Vec<_>>().join(", "))));
+                };
+                sections.push(Section { name, first_line:line_number, lines:Vec::new() });
+                in_section = true;
+            }
+            sections.last_mut().unwrap().lines.push(line);
+        }
+        if sections.len() < required.len() {
+            return Err(InputError::new(line_count, format!("missing section '{}'", required[sections.len()])));
+        }
+        Ok(Sections { sections })
+    }
+
+    // a required section, always there after a successful read
+    pub fn get(&self, name:&str) -> &Section<'a> {
+        self.optional(name).unwrap_or_else(|| panic!("There is no section '{}'", name))
+    }
+
+    pub fn optional(&self, name:&str) -> Option<&Section<'a>> {
+        self.sections.iter().find(|section| section.name == name)
     }
-    assert!(parts.next().is_none());
-    sections
 }
 
 #[test]
-fn test_split_input_sections() {
+fn test_sections() {
     let input =
 "FOO
-BAR
+BAR\r
 HOSE
+\r
 
 Blumenkohl
-Sahnetorte";
-    let parts = split_input_sections::<2>(input);
-    assert_eq!(parts[0], "FOO\nBAR\nHOSE".to_string());
-    assert_eq!(parts[1], "Blumenkohl\nSahnetorte".to_string());
-}
-
-// split a list of strings at empty string
-// often used to seperate parts of the input file for advent of code puzzles
-pub fn split_lines_sections<const SECTION_COUNT:usize>(lines:Vec<String>) -> [Vec<String>;SECTION_COUNT] {
-    let mut section = 0;
-    const EMPTY_VECTOR:Vec<String> = Vec::new();
-    let mut sections:[Vec<String>;SECTION_COUNT] = [EMPTY_VECTOR;SECTION_COUNT];
-    for line in lines {
-        if line == "" {
-            section += 1;
-        } else {
-            sections[section].push(line);
-        }
-    }
-    assert_eq!(section, SECTION_COUNT - 1);
-    sections
-}
+Sahnetorte
+";
+    let sections = Sections::read(input.split('\n'), &["words", "food"], &[]).unwrap();
+    assert_eq!(sections.get("words").lines, vec!["FOO", "BAR", "HOSE"]);
+    assert_eq!(sections.get("food").first_line, 6);
+    assert_eq!(sections.get("food").expect_line_count(2), Ok(()));
+    assert_eq!(sections.get("food").expect_line_count(1), Err(InputError::new(6, "section 'food' has 2 lines instead of 1".to_string())));
+    assert_eq!(sections.get("food").parse(|text| Ok(text.to_string())), Ok("Blumenkohl\nSahnetorte".to_string()));
+    assert_eq!(sections.get("food").parse(|_| Err::<(), _>(InputError::new(2, "no dessert".to_string()))).unwrap_err().line, 7);
+    assert_eq!(sections.optional("drinks"), None);
+
+    let sections = Sections::read(input.split('\n'), &["words"], &["food", "drinks"]).unwrap();
+    assert_eq!(sections.optional("food").unwrap().lines.len(), 2);
+    assert_eq!(sections.optional("drinks"), None);
 
+    let too_few = Sections::read(input.split('\n'), &["words", "food", "drinks"], &[]);
+    assert_eq!(too_few.unwrap_err(), InputError::new(8, "missing section 'drinks'".to_string()));
+
+    let too_many = Sections::read(input.split('\n'), &["words"], &[]);
+    assert_eq!(too_many.unwrap_err().to_string(), "line 6: unexpected section, expected at most 1 sections (words)");
+}
 
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 
-pub fn read_file(filename:&str) -> Vec<String> {
-    let file = File::open(filename).unwrap_or_else(|_| panic!("Could not open {}", filename));
-    let reader = BufReader::new(file);
+pub fn read_file_to_string(filename:&str) -> String {
+    std::fs::read_to_string(filename).unwrap_or_else(|_| panic!("Could not open {}", filename))
+}
+
+// read lines one by one instead of loading the whole file
+// the returned lines borrow an internal buffer which is reused for every line,
+// so only the current line is kept in memory
+// sections are split like in Sections: runs of empty lines count as one separator,
+// empty lines at begin and end are ignored
+pub struct LineReader<R:BufRead> {
+    reader:R,
+    buffer:String,
+    // number of the line in buffer, starting with 1
+    line_number:usize,
+    // buffer holds the first line of the current section, which was not returned yet
+    pending:bool,
+    // an empty line was read, the current section is finished
+    section_done:bool,
+    file_done:bool
+}
+
+impl LineReader<BufReader<File>> {
+    pub fn open(filename:&str) -> Self {
+        let file = File::open(filename).unwrap_or_else(|_| panic!("Could not open {}", filename));
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R:BufRead> LineReader<R> {
+    pub fn new(reader:R) -> Self {
+        LineReader { reader, buffer:String::new(), line_number:0, pending:false, section_done:false, file_done:false }
+    }
+
+    // return false at the end of the file
+    fn read_line(&mut self) -> bool {
+        self.buffer.clear();
+        let bytes = self.reader.read_line(&mut self.buffer).unwrap_or_else(|err| panic!("Could not read line {}: {}", self.line_number + 1, err));
+        if bytes == 0 {
+            self.file_done = true;
+            return false;
+        }
+        self.line_number += 1;
+        true
+    }
 
-    let lines:Vec<String> = reader.lines().map( |line| line.unwrap() ).collect();
-    lines
+    fn current_line(&self) -> &str {
+        self.buffer.trim_end_matches('\n').trim_end_matches('\r')
+    }
+
+    // return false if only empty lines are left
+    fn skip_empty_lines(&mut self) -> bool {
+        while self.read_line() {
+            if !self.current_line().is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+
+    // the number of the line, starting with 1, and the line
+    // return None at the end of the current section or at the end of the file
+    pub fn next_line(&mut self) -> Option<(usize, &str)> {
+        if self.section_done || self.file_done {
+            return None;
+        }
+        if self.pending {
+            self.pending = false;
+        } else {
+            let found = if self.line_number == 0 { self.skip_empty_lines() } else { self.read_line() };
+            if !found {
+                return None;
+            }
+            if self.current_line().is_empty() {
+                self.section_done = true;
+                return None;
+            }
+        }
+        Some((self.line_number, self.current_line()))
+    }
+
+    // skip the rest of the current section and the empty lines after it
+    // return false if there is no further section
+    pub fn next_section(&mut self) -> bool {
+        while self.next_line().is_some() {}
+        if self.file_done || !self.skip_empty_lines() {
+            return false;
+        }
+        self.section_done = false;
+        self.pending = true;
+        true
+    }
+}
+
+#[test]
+fn test_line_reader() {
+    let input =
+"FOO
+BAR\r
+HOSE
+
+Blumenkohl
+Sahnetorte
+";
+    let mut reader = LineReader::new(input.as_bytes());
+    assert_eq!(reader.next_line(), Some((1, "FOO")));
+    assert_eq!(reader.next_line(), Some((2, "BAR")));
+    assert!(reader.next_section());
+    assert_eq!(reader.next_line(), Some((5, "Blumenkohl")));
+    assert_eq!(reader.next_line(), Some((6, "Sahnetorte")));
+    assert_eq!(reader.next_line(), None);
+    assert_eq!(reader.next_line(), None);
+    assert!(!reader.next_section());
+}
+
+#[test]
+fn test_line_reader_like_sections() {
+    // all sections of the reader with the line numbers of their first lines
+    fn read_all(input:&str) -> Vec<(usize, Vec<String>)> {
+        let mut reader = LineReader::new(input.as_bytes());
+        let mut sections = Vec::new();
+        loop {
+            let mut lines = Vec::new();
+            let mut first_line = 0;
+            while let Some((line_number, line)) = reader.next_line() {
+                if lines.is_empty() { first_line = line_number; }
+                lines.push(line.to_string());
+            }
+            if !lines.is_empty() { sections.push((first_line, lines)); }
+            if !reader.next_section() { return sections; }
+        }
+    }
+    for input in ["a\nb\n\nc\n", "\n\na\n\n\n\nb\nc\n\n\n", "a\r\n\r\n\r\nb\r\n\r\n", "a", "\n\n", ""] {
+        let sections = Sections::read(input.lines(), &[], &["1", "2", "3"]).unwrap();
+        let expected:Vec<(usize, Vec<String>)> = sections.sections.iter()
+            .map(|section| (section.first_line, section.lines.iter().map(|line| line.to_string()).collect()))
+            .collect();
+        assert_eq!(read_all(input), expected, "input {:?}", input);
+    }
+    // the empty lines at the end do not start another section
+    let mut reader = LineReader::new("a\n\n\nb\n\n".as_bytes());
+    assert!(reader.next_section());
+    assert_eq!(reader.next_line(), Some((4, "b")));
+    assert!(!reader.next_section());
+    assert_eq!(reader.next_line(), None);
 }
\ No newline at end of file
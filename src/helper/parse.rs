@@ -0,0 +1,193 @@
+// Parsing of typical input lines
+//
+// All functions report the column of the problem, starting with 1.
+// The column is relative to the text given to the function.
+
+use std::any::type_name;
+use std::str::FromStr;
+
+use pest::iterators::Pair;
+
+use crate::helper::InputError;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub column:usize,
+    pub message:String
+}
+
+impl ParseError {
+    pub fn new(column:usize, message:String) -> Self {
+        ParseError { column, message }
+    }
+
+    // attach the line number
+    pub fn at_line(self, line:usize) -> InputError {
+        InputError { line, column:Some(self.column), length:1, message:self.message }
+    }
+
+    // the parsed text was matched by a pest pair
+    pub fn at_pair<R:pest::RuleType>(self, pair:&Pair<'_, R>) -> InputError {
+        let (line, column) = pair.line_col();
+        self.shifted(column - 1).at_line(line)
+    }
+
+    // the parsed text started at column offset+1 of the line
+    pub fn shifted(self, offset:usize) -> Self {
+        ParseError { column:self.column + offset, message:self.message }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+// column of part in text, part must be a slice of text
+fn column_of(text:&str, part:&str) -> usize {
+    part.as_ptr() as usize - text.as_ptr() as usize + 1
+}
+
+// "42" -> 42
+pub fn value<T:FromStr>(text:&str) -> Result<T, ParseError> {
+    text.parse::<T>().map_err(|_| ParseError::new(1, format!("'{}' is no {}", text, type_name::<T>())))
+}
+
+// all signed integers in a line
+// "p=0,4 v=3,-3" -> [0, 4, 3, -3]
+pub fn integers<T:FromStr>(line:&str) -> Result<Vec<T>, ParseError> {
+    let bytes = line.as_bytes();
+    let mut integers = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let is_minus = bytes[index] == b'-' && index + 1 < bytes.len() && bytes[index + 1].is_ascii_digit();
+        if !is_minus && !bytes[index].is_ascii_digit() {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        index += 1;
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+        integers.push(value(&line[start..index]).map_err(|err| err.shifted(start))?);
+    }
+    Ok(integers)
+}
+
+// "7,6,4" -> [7, 6, 4]
+pub fn list<T:FromStr>(text:&str, separator:&str) -> Result<Vec<T>, ParseError> {
+    Ok(list_with_columns(text, separator)?.into_iter().map(|(_, value)| value).collect())
+}
+
+// like list, but with the column of each value for later checks
+// "7,16,4" -> [(1, 7), (3, 16), (6, 4)]
+pub fn list_with_columns<T:FromStr>(text:&str, separator:&str) -> Result<Vec<(usize, T)>, ParseError> {
+    text.split(separator)
+        .map(|part| {
+            let column = column_of(text, part);
+            Ok((column, value(part).map_err(|err| err.shifted(column - 1))?))
+        })
+        .collect()
+}
+
+// split into exactly N parts
+// "kh-tc" -> ["kh", "tc"]
+pub fn fields<'a, const N:usize>(line:&'a str, separator:&str) -> Result<[&'a str;N], ParseError> {
+    let parts:Vec<&str> = line.split(separator).collect();
+    if parts.len() != N {
+        let column = if parts.len() > N { column_of(line, parts[N]) } else { line.len() + 1 };
+        return Err(ParseError::new(column, format!("expected {} parts separated by '{}', found {}", N, separator, parts.len())));
+    }
+    Ok(parts.try_into().unwrap())
+}
+
+// "5,4" -> (5, 4)
+pub fn pair<T:FromStr>(line:&str, separator:&str) -> Result<(T, T), ParseError> {
+    let [a, b] = fields::<2>(line, separator)?;
+    Ok((value(a)?, value(b).map_err(|err| err.shifted(column_of(line, b) - 1))?))
+}
+
+// "Register A: 729" -> ("Register A", 729)
+pub fn register<T:FromStr>(line:&str) -> Result<(&str, T), ParseError> {
+    let [key, text] = fields::<2>(line, ": ")?;
+    Ok((key, value(text).map_err(|err| err.shifted(column_of(line, text) - 1))?))
+}
+
+// parse every line of a line-oriented input, empty lines at the end are ignored
+// "3   4\n4   3" -> [parse("3   4"), parse("4   3")]
+pub fn parse_lines<T>(input:&str, parse:impl Fn(&str) -> Result<T, ParseError>) -> Result<Vec<T>, InputError> {
+    input.trim_end_matches(['\r', '\n']).lines().enumerate()
+        .map(|(index, line)| parse(line).map_err(|err| err.at_line(index + 1)))
+        .collect()
+}
+
+// parse the input with a pest grammar and return the pair matched by rule
+pub fn parse_rule<P:pest::Parser<R>, R:pest::RuleType>(rule:R, input:&str) -> Result<Pair<'_, R>, InputError> {
+    pest::set_error_detail(true);
+    match P::parse(rule, input) {
+        Ok(mut pairs) => Ok(pairs.next().unwrap()),
+        Err(error) => Err(InputError::from_pest(error, input, rule))
+    }
+}
+
+// the rows of a map, matched by row_rule inside of file_rule
+// all rows must have the same length
+pub fn map_rows<'a, R:pest::RuleType>(file_rule:Pair<'a, R>, row_rule:R) -> Result<Vec<&'a str>, InputError> {
+    let mut rows:Vec<&str> = Vec::new();
+    for pair in file_rule.into_inner().filter(|pair| pair.as_rule() == row_rule) {
+        let row = pair.as_str();
+        if let Some(first) = rows.first() {
+            if row.len() != first.len() {
+                let column = first.len().min(row.len()) + 1;
+                return Err(ParseError::new(column, format!("row has {} characters instead of {}", row.len(), first.len())).at_pair(&pair));
+            }
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[test]
+fn test_map_rows() {
+    use pest::Parser;
+    use crate::helper::test_grammar::{Rule, TestParser};
+
+    let file = TestParser::parse(Rule::file, "123\n456").unwrap().next().unwrap();
+    assert_eq!(map_rows(file, Rule::number), Ok(vec!["123", "456"]));
+
+    let file = TestParser::parse(Rule::file, "123\n456\n78").unwrap().next().unwrap();
+    assert_eq!(map_rows(file, Rule::number).unwrap_err().to_string(), "line 3, column 3: row has 2 characters instead of 3");
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(value::<u32>("42"), Ok(42));
+    assert_eq!(value::<u32>("-42"), Err(ParseError::new(1, "'-42' is no u32".to_string())));
+
+    assert_eq!(integers::<i32>("p=0,4 v=3,-3"), Ok(vec![0, 4, 3, -3]));
+    assert_eq!(integers::<i32>("a-b 12-7"), Ok(vec![12, -7]));
+    assert_eq!(integers::<u8>("Button A: X+94, Y+342"), Err(ParseError::new(19, "'342' is no u8".to_string())));
+
+    assert_eq!(list::<u32>("7 6 4", " "), Ok(vec![7, 6, 4]));
+    assert_eq!(list::<u32>("75,47,x1", ","), Err(ParseError::new(7, "'x1' is no u32".to_string())));
+    assert_eq!(list_with_columns::<u32>("7,16,4", ","), Ok(vec![(1, 7), (3, 16), (6, 4)]));
+
+    assert_eq!(fields::<2>("kh-tc", "-"), Ok(["kh", "tc"]));
+    assert_eq!(fields::<2>("kh-tc-ab", "-"), Err(ParseError::new(7, "expected 2 parts separated by '-', found 3".to_string())));
+    assert_eq!(fields::<3>("kh-tc", "-"), Err(ParseError::new(6, "expected 3 parts separated by '-', found 2".to_string())));
+
+    assert_eq!(pair::<usize>("5,4", ","), Ok((5, 4)));
+    assert_eq!(pair::<usize>("5,a", ",").unwrap_err().column, 3);
+
+    assert_eq!(register::<u32>("Register A: 729"), Ok(("Register A", 729)));
+    assert_eq!(register::<u32>("Register A: 7x9").unwrap_err().at_line(2).to_string(), "line 2, column 13: '7x9' is no u32");
+
+    assert_eq!(parse_lines("5,4\r\n4,2\n\n", |line| pair::<usize>(line, ",")), Ok(vec![(5, 4), (4, 2)]));
+    assert_eq!(parse_lines("", value::<u32>), Ok(vec![]));
+    assert_eq!(parse_lines("5,4\n4,x", |line| pair::<usize>(line, ",")).unwrap_err().to_string(), "line 2, column 3: 'x' is no usize");
+}
@@ -0,0 +1,429 @@
+// Image files of maps
+//
+// The formats are simple enough to be written by hand, no image library is needed.
+// PPM and PGM are the binary variants (P6 and P5) of the netpbm formats.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use crate::maps::{PixelMap, Point};
+
+//////////////////////////////////////////
+/// Colors
+//////////////////////////////////////////
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK:Rgb = Rgb(0, 0, 0);
+    pub const WHITE:Rgb = Rgb(255, 255, 255);
+    pub const GRAY:Rgb = Rgb(128, 128, 128);
+
+    // the luminance (ITU-R BT.601)
+    #[allow(dead_code)]
+    pub fn gray(self) -> u8 {
+        ((299 * self.0 as u32 + 587 * self.1 as u32 + 114 * self.2 as u32) / 1000) as u8
+    }
+}
+
+// distinct colors for numbered things like regions, repeating after 12
+pub fn palette(index:usize) -> Rgb {
+    const COLORS:[Rgb;12] = [
+        Rgb(230, 25, 75), Rgb(60, 180, 75), Rgb(255, 225, 25), Rgb(0, 130, 200),
+        Rgb(245, 130, 48), Rgb(145, 30, 180), Rgb(70, 240, 240), Rgb(240, 50, 230),
+        Rgb(210, 245, 60), Rgb(250, 190, 212), Rgb(0, 128, 128), Rgb(170, 110, 40)
+    ];
+    COLORS[index % COLORS.len()]
+}
+
+pub trait ToColor {
+    fn to_color(self) -> Rgb;
+}
+
+impl ToColor for bool {
+    fn to_color(self) -> Rgb {
+        if self { Rgb::WHITE } else { Rgb::BLACK }
+    }
+}
+
+// gray levels
+impl ToColor for u8 {
+    fn to_color(self) -> Rgb {
+        Rgb(self, self, self)
+    }
+}
+
+// e.g. component labels
+impl ToColor for usize {
+    fn to_color(self) -> Rgb {
+        palette(self)
+    }
+}
+
+// empty is black, walls are gray, everything else gets a color of the palette
+impl ToColor for char {
+    fn to_color(self) -> Rgb {
+        match self {
+            '.' | ' ' => Rgb::BLACK,
+            '#' => Rgb::GRAY,
+            c => palette(c as usize)
+        }
+    }
+}
+
+//////////////////////////////////////////
+/// PPM and PGM
+//////////////////////////////////////////
+impl<E:ToColor+Copy> PixelMap<E> {
+    // every pixel becomes a square of scale * scale pixels
+    fn write_netpbm(&self, mut writer:impl Write, magic:&str, scale:usize, bytes:impl Fn(Rgb) -> Vec<u8>) -> std::io::Result<()> {
+        assert!(scale > 0, "The scale must be at least 1");
+        write!(writer, "{}\n{} {}\n255\n", magic, self.area.width * scale, self.area.height * scale)?;
+        for y in 0..self.area.height {
+            let mut row = Vec::new();
+            for x in 0..self.area.width {
+                let pixel = bytes(self[Point(x, y)].to_color());
+                for _ in 0..scale {
+                    row.extend_from_slice(&pixel);
+                }
+            }
+            for _ in 0..scale {
+                writer.write_all(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_ppm(&self, writer:impl Write, scale:usize) -> std::io::Result<()> {
+        self.write_netpbm(writer, "P6", scale, |color| vec![color.0, color.1, color.2])
+    }
+
+    #[allow(dead_code)]
+    pub fn write_pgm(&self, writer:impl Write, scale:usize) -> std::io::Result<()> {
+        self.write_netpbm(writer, "P5", scale, |color| vec![color.gray()])
+    }
+}
+
+// writes frames/{name}_{index}.ppm, e.g. to look at many frames with an image viewer
+pub fn write_frame<E:ToColor+Copy>(map:&PixelMap<E>, name:&str, index:usize, scale:usize) -> std::io::Result<()> {
+    fs::create_dir_all("frames")?;
+    let mut writer = BufWriter::new(File::create(format!("frames/{}_{:05}.ppm", name, index))?);
+    map.write_ppm(&mut writer, scale)?;
+    writer.flush()
+}
+
+#[test]
+fn test_netpbm() {
+    let map = PixelMap::<bool>::from_fn(2, 1, |pos| pos.0 == 1);
+    let mut ppm = Vec::new();
+    map.write_ppm(&mut ppm, 1).unwrap();
+    assert_eq!(ppm, b"P6\n2 1\n255\n\x00\x00\x00\xff\xff\xff");
+
+    let mut scaled = Vec::new();
+    map.write_pgm(&mut scaled, 2).unwrap();
+    assert_eq!(scaled, b"P5\n4 2\n255\n\x00\x00\xff\xff\x00\x00\xff\xff");
+
+    let labels = PixelMap::<usize>::new(1, 1, 1);
+    let mut gray = Vec::new();
+    labels.write_pgm(&mut gray, 1).unwrap();
+    assert_eq!(gray, [b"P5\n1 1\n255\n".as_slice(), &[palette(1).gray()]].concat());
+    assert_eq!(Rgb::WHITE.gray(), 255);
+    assert_eq!(Rgb(0, 255, 0).gray(), 149);
+
+    assert_eq!('.'.to_color(), Rgb::BLACK);
+    assert_eq!('*'.to_color(), palette('*' as usize));
+    assert_eq!(palette(12), palette(0));
+}
+
+#[test]
+#[should_panic]
+fn test_netpbm_scale_zero() {
+    PixelMap::<bool>::new(1, 1, true).write_ppm(Vec::new(), 0).unwrap();
+}
+
+//////////////////////////////////////////
+/// GIF
+//////////////////////////////////////////
+fn invalid_input(message:&str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
+
+// variable length codes, least significant bit first, in sub-blocks of at most 255 bytes
+struct BitWriter {
+    bytes:Vec<u8>,
+    buffer:u32,
+    bits:u32
+}
+
+impl BitWriter {
+    fn write(&mut self, code:u16, size:u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 { self.bytes.push(self.buffer as u8); }
+        let mut blocks = Vec::new();
+        for block in self.bytes.chunks(255) {
+            blocks.push(block.len() as u8);
+            blocks.extend_from_slice(block);
+        }
+        blocks.push(0);
+        blocks
+    }
+}
+
+// the LZW variant of GIF: codes grow from min_code_size + 1 up to 12 bits, then the table is cleared
+fn lzw_encode(indices:&[u8], min_code_size:u32) -> Vec<u8> {
+    const MAX_CODE:u16 = 4096;
+    let clear_code:u16 = 1 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut table:HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+    let mut output = BitWriter { bytes:Vec::new(), buffer:0, bits:0 };
+    output.write(clear_code, code_size);
+    let Some((&first, rest)) = indices.split_first() else {
+        output.write(end_code, code_size);
+        return output.finish();
+    };
+    let mut current = first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(current, index)) {
+            current = code;
+            continue;
+        }
+        output.write(current, code_size);
+        if next_code == MAX_CODE {
+            output.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        } else {
+            if next_code >= 1 << code_size { code_size += 1; }
+            table.insert((current, index), next_code);
+            next_code += 1;
+        }
+        current = index as u16;
+    }
+    output.write(current, code_size);
+    output.write(end_code, code_size);
+    output.finish()
+}
+
+// an animated GIF89a which loops forever, each frame has its own color table
+pub struct GifWriter<W:Write> {
+    writer:W,
+    delay:u16, // in hundredths of a second
+    scale:usize,
+    size:Option<(u16, u16)> // of the first frame
+}
+
+impl<W:Write> GifWriter<W> {
+    pub fn new(writer:W, delay:u16, scale:usize) -> Self {
+        assert!(scale > 0, "The scale must be at least 1");
+        GifWriter { writer, delay, scale, size:None }
+    }
+
+    fn write_header(&mut self, width:u16, height:u16) -> io::Result<()> {
+        self.writer.write_all(b"GIF89a")?;
+        self.writer.write_all(&width.to_le_bytes())?;
+        self.writer.write_all(&height.to_le_bytes())?;
+        // no global color table, background 0, no aspect ratio
+        self.writer.write_all(&[0, 0, 0])?;
+        // loop forever
+        self.writer.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")
+    }
+
+    // all frames must have the same size and at most 256 colors
+    pub fn add_frame<E:ToColor+Copy>(&mut self, frame:&PixelMap<E>) -> io::Result<()> {
+        let too_large = || invalid_input("The frame is too large for a GIF");
+        let width = u16::try_from(frame.area.width * self.scale).map_err(|_| too_large())?;
+        let height = u16::try_from(frame.area.height * self.scale).map_err(|_| too_large())?;
+        match self.size {
+            None => {
+                self.write_header(width, height)?;
+                self.size = Some((width, height));
+            },
+            Some(size) if size != (width, height) => { return Err(invalid_input("All frames of a GIF must have the same size")); },
+            Some(_) => {}
+        }
+
+        let mut colors:Vec<Rgb> = Vec::new();
+        let mut indices:Vec<u8> = Vec::with_capacity(width as usize * height as usize);
+        let mut row:Vec<u8> = Vec::with_capacity(width as usize);
+        for y in 0..frame.area.height {
+            row.clear();
+            for x in 0..frame.area.width {
+                let color = frame[Point(x, y)].to_color();
+                let index = match colors.iter().position(|&known| known == color) {
+                    Some(index) => index,
+                    None => {
+                        colors.push(color);
+                        colors.len() - 1
+                    }
+                };
+                let index = u8::try_from(index).map_err(|_| invalid_input("A GIF frame can only have 256 colors"))?;
+                row.extend(std::iter::repeat_n(index, self.scale));
+            }
+            for _ in 0..self.scale {
+                indices.extend_from_slice(&row);
+            }
+        }
+        // the color table has 2^bits entries, at least 2
+        let bits = (1..=8).find(|&bits| colors.len() <= 1 << bits).unwrap();
+
+        let [delay_low, delay_high] = self.delay.to_le_bytes();
+        self.writer.write_all(&[0x21, 0xf9, 4, 0x04, delay_low, delay_high, 0, 0])?;
+        self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.writer.write_all(&width.to_le_bytes())?;
+        self.writer.write_all(&height.to_le_bytes())?;
+        self.writer.write_all(&[0x80 | (bits - 1) as u8])?;
+        for index in 0..1 << bits {
+            let color = colors.get(index).copied().unwrap_or(Rgb::BLACK);
+            self.writer.write_all(&[color.0, color.1, color.2])?;
+        }
+        let min_code_size = bits.max(2);
+        self.writer.write_all(&[min_code_size as u8])?;
+        self.writer.write_all(&lzw_encode(&indices, min_code_size))
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.size.is_none() { return Err(invalid_input("A GIF needs at least one frame")); }
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+// all frames at once, a GifWriter from create_animation takes them one by one
+#[allow(dead_code)]
+pub fn write_gif<E:ToColor+Copy>(writer:impl Write, frames:impl IntoIterator<Item=PixelMap<E>>, delay:u16, scale:usize) -> io::Result<()> {
+    let mut gif = GifWriter::new(writer, delay, scale);
+    for frame in frames {
+        gif.add_frame(&frame)?;
+    }
+    gif.finish()?;
+    Ok(())
+}
+
+// writes frames/{name}.gif
+pub fn create_animation(name:&str, delay:u16, scale:usize) -> io::Result<GifWriter<BufWriter<File>>> {
+    fs::create_dir_all("frames")?;
+    Ok(GifWriter::new(BufWriter::new(File::create(format!("frames/{}.gif", name))?), delay, scale))
+}
+
+// the frames as color indices and their color tables
+#[cfg(test)]
+fn read_gif(bytes:&[u8]) -> Vec<(Vec<u8>, Vec<Rgb>)> {
+    fn sub_blocks(bytes:&[u8], pos:&mut usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        while bytes[*pos] != 0 {
+            let len = bytes[*pos] as usize;
+            data.extend_from_slice(&bytes[*pos + 1..*pos + 1 + len]);
+            *pos += len + 1;
+        }
+        *pos += 1;
+        data
+    }
+    fn lzw_decode(data:&[u8], min_code_size:u32) -> Vec<u8> {
+        let clear_code = 1usize << min_code_size;
+        let mut table:Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut previous:Option<usize> = None;
+        let (mut bit, mut output) = (0, Vec::new());
+        loop {
+            let mut code = 0;
+            for i in 0..code_size as usize {
+                code |= (((data[(bit + i) / 8] >> ((bit + i) % 8)) & 1) as usize) << i;
+            }
+            bit += code_size as usize;
+            if code == clear_code {
+                table = (0..clear_code + 2).map(|i| vec![i as u8]).collect();
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear_code + 1 { return output; }
+            let entry = match previous {
+                None => table[code].clone(),
+                Some(previous) => {
+                    let entry = if code < table.len() { table[code].clone() } else { [table[previous].clone(), vec![table[previous][0]]].concat() };
+                    table.push([table[previous].clone(), vec![entry[0]]].concat());
+                    if table.len() == 1 << code_size && code_size < 12 { code_size += 1; }
+                    entry
+                }
+            };
+            output.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    assert_eq!(&bytes[..6], b"GIF89a");
+    let mut pos = 13;
+    let mut frames = Vec::new();
+    loop {
+        match bytes[pos] {
+            0x21 => { pos += 2; sub_blocks(bytes, &mut pos); },
+            0x2c => {
+                let bits = (bytes[pos + 9] & 7) as usize + 1;
+                pos += 10;
+                let colors = (0..1 << bits).map(|i| Rgb(bytes[pos + 3 * i], bytes[pos + 3 * i + 1], bytes[pos + 3 * i + 2])).collect();
+                pos += 3 << bits;
+                let min_code_size = bytes[pos] as u32;
+                pos += 1;
+                frames.push((lzw_decode(&sub_blocks(bytes, &mut pos), min_code_size), colors));
+            },
+            0x3b => { return frames; },
+            other => panic!("Unexpected block {:x}", other)
+        }
+    }
+}
+
+#[test]
+fn test_gif() {
+    let frame = |second:usize| PixelMap::<char>::from_fn(3, 2, |pos| if pos.0 == second % 3 { '*' } else { '.' });
+    let mut gif = Vec::new();
+    write_gif(&mut gif, (0..3).map(frame), 10, 1).unwrap();
+    assert_eq!(&gif[6..10], [3, 0, 2, 0]);
+    assert_eq!(gif.last(), Some(&0x3b));
+    let frames = read_gif(&gif);
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[1].0, [0, 1, 0, 0, 1, 0]);
+    assert_eq!(frames[1].1, [Rgb::BLACK, '*'.to_color()]);
+
+    // scaled
+    let mut scaled = Vec::new();
+    write_gif(&mut scaled, [frame(0)], 10, 2).unwrap();
+    assert_eq!(read_gif(&scaled)[0].0, [[0, 0, 1, 1, 1, 1]; 4].concat());
+
+    // long enough to fill the code table several times
+    let noise = PixelMap::<usize>::from_fn(200, 100, |pos| (pos.0 * 7919 + pos.1 * pos.1 * 104729) % 256);
+    let palette_colors:Vec<Rgb> = (0..12).map(palette).collect();
+    let mut gif = Vec::new();
+    write_gif(&mut gif, [noise.clone()], 0, 1).unwrap();
+    let (indices, colors) = &read_gif(&gif)[0];
+    let decoded:Vec<Rgb> = indices.iter().map(|&index| colors[index as usize]).collect();
+    let expected:Vec<Rgb> = noise.area.all_positions().map(|pos| noise[pos].to_color()).collect();
+    assert_eq!(decoded, expected);
+    assert_eq!(colors.len(), 16);
+    assert!(palette_colors.iter().all(|color| colors.contains(color)));
+
+    let gray = PixelMap::<u8>::from_fn(256, 64, |pos| (pos.0 as u8) ^ (pos.1 as u8));
+    let mut gif = Vec::new();
+    write_gif(&mut gif, [gray.clone()], 0, 1).unwrap();
+    let (indices, colors) = &read_gif(&gif)[0];
+    assert_eq!(colors.len(), 256);
+    assert!(gray.area.all_positions().zip(indices).all(|(pos, &index)| colors[index as usize] == gray[pos].to_color()));
+
+    assert!(write_gif(Vec::new(), [frame(0), PixelMap::new(2, 2, '.')], 10, 1).is_err());
+    assert!(write_gif(Vec::new(), Vec::<PixelMap<char>>::new(), 10, 1).is_err());
+    assert!(write_gif(Vec::new(), [PixelMap::<usize>::from_fn(20, 20, |pos| pos.0 * 20 + pos.1)], 10, 1).is_ok());
+}
@@ -1,6 +1,9 @@
 mod maps;
 mod optimize;
 mod helper;
+mod trace;
+mod render;
+mod image;
 
 mod day1;
 mod day2;
@@ -29,17 +32,43 @@ mod day23;
 
 const NUMBER_OF_DAYS : u32 = 22;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The days to handle
-    days: Vec<u32>
+    days: Vec<u32>,
+
+    /// Record the steps of the simulations (days 6, 14, 15 and 17) as JSON Lines
+    #[arg(long, value_name = "FILE")]
+    trace: Option<String>
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Step forwards and backwards through a recorded trace
+    Replay {
+        file: String
+    }
 }
 
 fn main() {
     let args = Cli::parse();
 
+    if let Some(Command::Replay { file }) = args.command {
+        trace::replay(&file);
+        return;
+    }
+
+    let mut recorder = match &args.trace {
+        Some(filename) => trace::Recorder::create(filename),
+        None => trace::Recorder::disabled()
+    };
+
     let days = if !args.days.is_empty() { args.days } else { (1..NUMBER_OF_DAYS+1).collect() };
 
     for day in days {
@@ -49,7 +78,7 @@ fn main() {
             3=>day3::puzzle(),
             4=>day4::puzzle(),
             5=>day5::puzzle(),
-            6=>day6::puzzle(),
+            6=>day6::puzzle(&mut recorder),
             7=>day7::puzzle(),
             8=>day8::puzzle(),
             9=>day9::puzzle(),
@@ -57,10 +86,10 @@ fn main() {
             11=>day11::puzzle(),
             12=>day12::puzzle(),
             13=>day13::puzzle(),
-            14=>day14::puzzle(),
-            15=>day15::puzzle(),
+            14=>day14::puzzle(&mut recorder),
+            15=>day15::puzzle(&mut recorder),
             16=>day16::puzzle(),
-            17=>day17::puzzle(),
+            17=>day17::puzzle(&mut recorder),
             18=>day18::puzzle(),
             19=>day19::puzzle(),
             20=>day20::puzzle(),
@@ -71,4 +100,6 @@ fn main() {
             otherday=>println!("Unknown day {}", otherday)
         }
     }
+
+    recorder.finish();
 }
@@ -1,9 +1,113 @@
-pub type Position = (/* x: */usize,/* y: */usize);
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::ops::{Add, Index, IndexMut, Mul, Sub};
+use std::str::FromStr;
 
-pub fn left (pos:Position) -> Position { (pos.0-1,pos.1  ) }
-pub fn right(pos:Position) -> Position { (pos.0+1,pos.1  ) }
-pub fn up   (pos:Position) -> Position { (pos.0  ,pos.1-1) }
-pub fn down (pos:Position) -> Position { (pos.0  ,pos.1+1) }
+use crate::helper::InputError;
+
+//////////////////////////////////////////
+/// Point and Vector
+//////////////////////////////////////////
+
+// a location, e.g. on a map
+#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone, Default, PartialOrd, Ord)]
+pub struct Point<T>(/* x: */pub T, /* y: */pub T);
+
+// the difference between two points
+#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone, Default, PartialOrd, Ord)]
+pub struct Vector<T>(/* dx: */pub T, /* dy: */pub T);
+
+pub type Position = Point<usize>;
+
+pub fn left (pos:Position) -> Position { Point(pos.0-1,pos.1  ) }
+pub fn right(pos:Position) -> Position { Point(pos.0+1,pos.1  ) }
+pub fn up   (pos:Position) -> Position { Point(pos.0  ,pos.1-1) }
+pub fn down (pos:Position) -> Position { Point(pos.0  ,pos.1+1) }
+
+// works for unsigned types too
+fn abs_diff<T:Sub<Output=T>+PartialOrd>(a:T, b:T) -> T {
+    if a > b { a - b } else { b - a }
+}
+
+impl<T:Copy+Sub<Output=T>+Add<Output=T>+PartialOrd> Point<T> {
+    pub fn manhattan(self, other:Point<T>) -> T {
+        abs_diff(self.0, other.0) + abs_diff(self.1, other.1)
+    }
+
+    #[allow(dead_code)]
+    pub fn chebyshev(self, other:Point<T>) -> T {
+        let dx = abs_diff(self.0, other.0);
+        let dy = abs_diff(self.1, other.1);
+        if dx > dy { dx } else { dy }
+    }
+}
+
+impl<T> Point<T> {
+    // e.g. signed to unsigned, None if a coordinate does not fit
+    pub fn cast<U:TryFrom<T>>(self) -> Option<Point<U>> {
+        Some(Point(U::try_from(self.0).ok()?, U::try_from(self.1).ok()?))
+    }
+}
+
+impl<T> Vector<T> {
+    pub fn cast<U:TryFrom<T>>(self) -> Option<Vector<U>> {
+        Some(Vector(U::try_from(self.0).ok()?, U::try_from(self.1).ok()?))
+    }
+}
+
+impl<T:Add<Output=T>> Add<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+    fn add(self, vector:Vector<T>) -> Point<T> { Point(self.0 + vector.0, self.1 + vector.1) }
+}
+
+impl<T:Sub<Output=T>> Sub<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+    fn sub(self, vector:Vector<T>) -> Point<T> { Point(self.0 - vector.0, self.1 - vector.1) }
+}
+
+impl<T:Sub<Output=T>> Sub<Point<T>> for Point<T> {
+    type Output = Vector<T>;
+    fn sub(self, other:Point<T>) -> Vector<T> { Vector(self.0 - other.0, self.1 - other.1) }
+}
+
+impl<T:Add<Output=T>> Add<Vector<T>> for Vector<T> {
+    type Output = Vector<T>;
+    fn add(self, other:Vector<T>) -> Vector<T> { Vector(self.0 + other.0, self.1 + other.1) }
+}
+
+impl<T:Sub<Output=T>> Sub<Vector<T>> for Vector<T> {
+    type Output = Vector<T>;
+    fn sub(self, other:Vector<T>) -> Vector<T> { Vector(self.0 - other.0, self.1 - other.1) }
+}
+
+impl<T:Mul<Output=T>+Copy> Mul<T> for Vector<T> {
+    type Output = Vector<T>;
+    fn mul(self, factor:T) -> Vector<T> { Vector(self.0 * factor, self.1 * factor) }
+}
+
+#[test]
+fn test_point() {
+    let a:Point<i32> = Point(2, 3);
+    let b = Point(-1, 7);
+    assert_eq!(b - a, Vector(-3, 4));
+    assert_eq!(a + (b - a) * 2, Point(-4, 11));
+    assert_eq!(a - Vector(1, 1), Point(1, 2));
+    assert_eq!(Vector(1, 2) + Vector(3, -4), Vector(4, -2));
+    assert_eq!(Vector(1, 2) - Vector(3, -4), Vector(-2, 6));
+    assert_eq!(a.manhattan(b), 7);
+    assert_eq!(a.chebyshev(b), 4);
+    assert_eq!(Point::<usize>(5, 1).manhattan(Point(2, 4)), 6);
+    assert_eq!(Point::<usize>(5, 1).chebyshev(Point(2, 3)), 3);
+    assert_eq!(a.cast::<usize>(), Some(Point(2, 3)));
+    assert_eq!(b.cast::<usize>(), None);
+    assert_eq!(Point::<usize>(4, 5).cast::<i64>(), Some(Point(4, 5)));
+    assert_eq!(Vector(-1i64, 0).cast::<i32>(), Some(Vector(-1, 0)));
+    assert_eq!(Vector(300i32, 0).cast::<u8>(), None);
+    assert_eq!(Point(1, 1) + Up.delta(), Point(1, 0));
+}
 
 //////////////////////////////////////////
 /// Direction
@@ -21,14 +125,28 @@ pub enum Direction {
     UpLeft,
 }
 
-impl FromChar for Direction {
-    fn from_char(c:char) -> Self {
+// the diagonals have no arrow of their own
+impl ToChar for Direction {
+    fn to_char(self) -> char {
+        match self {
+            Up    => '^',
+            Right => '>',
+            Down  => 'v',
+            Left  => '<',
+            UpRight | DownLeft => '/',
+            DownRight | UpLeft => '\\'
+        }
+    }
+}
+
+impl TryFromChar for Direction {
+    fn try_from_char(c:char) -> Option<Self> {
         match c {
-            '^' => Direction::Up,
-            '<' => Direction::Left,
-            '>' => Direction::Right,
-            'v' => Direction::Down,
-            _   => panic!("Unexpected direction character {}", c)
+            '^' => Some(Direction::Up),
+            '<' => Some(Direction::Left),
+            '>' => Some(Direction::Right),
+            'v' => Some(Direction::Down),
+            _   => None
         }
      }
 }
@@ -62,6 +180,26 @@ impl Direction {
     pub fn four_directions() -> [Direction;4] {
         [Right, Down, Left, Up]
     }
+
+    // clockwise like four_directions, each diagonal after its neighbour
+    pub fn eight_directions() -> [Direction;8] {
+        [Right, DownRight, Down, DownLeft, Left, UpLeft, Up, UpRight]
+    }
+
+    // one step in this direction, y grows downwards
+    pub fn delta<T:From<i8>>(&self) -> Vector<T> {
+        let (dx, dy) = match self {
+            Up    => ( 0,-1),
+            Right => ( 1, 0),
+            Down  => ( 0, 1),
+            Left  => (-1, 0),
+            UpRight   => ( 1,-1),
+            DownRight => ( 1, 1),
+            DownLeft  => (-1, 1),
+            UpLeft    => (-1,-1)
+        };
+        Vector(T::from(dx), T::from(dy))
+    }
 }
 
 use crate::maps::Direction::*;
@@ -75,11 +213,21 @@ fn test_direction() {
 /// Area
 //////////////////////////////////////////
 
-// some bounded area to move in
+// what happens at the edges of an area
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Topology {
+    // moving out of the area is not possible
+    Bounded,
+    // leaving on one side means entering on the opposite side
+    Toroidal
+}
+
+// some area to move in
 #[derive(PartialEq, Debug, Clone)]
 pub struct Area {
     pub width:usize,
-    pub height:usize
+    pub height:usize,
+    pub topology:Topology
 }
 
 pub struct AreaIterator<'a> {
@@ -95,7 +243,7 @@ impl Iterator for AreaIterator<'_> {
     type Item = Position;
     fn next(&mut self) -> Option<Position> {
         if self.done { return None; }
-        let pos=(self.x, self.y);
+        let pos=Point(self.x, self.y);
         if self.x < self.area.width-1  { self.x +=1; return Some(pos)};
         self.x = 0;
         if self.y < self.area.height-1 { self.y += 1; return Some(pos)};
@@ -106,65 +254,320 @@ impl Iterator for AreaIterator<'_> {
 }
 
 impl Area {
-    pub fn contains_signed(&self, x:i32, y:i32) -> bool {
-        x >= 0 && (x as usize) < self.width && y >= 0 && (y as usize) < self.height
+    pub fn new(width:usize, height:usize) -> Area {
+        Area { width, height, topology:Topology::Bounded }
+    }
+
+    pub fn toroidal(width:usize, height:usize) -> Area {
+        Area { width, height, topology:Topology::Toroidal }
+    }
+
+    pub fn contains(&self, pos:Position) -> bool {
+        pos.0 < self.width && pos.1 < self.height
+    }
+
+    // the position of a signed point, None if out of a bounded area
+    pub fn to_position(&self, point:Point<i64>) -> Option<Position> {
+        let width = i64::try_from(self.width).ok()?;
+        let height = i64::try_from(self.height).ok()?;
+        match self.topology {
+            Topology::Bounded => {
+                if (0..width).contains(&point.0) && (0..height).contains(&point.1) { point.cast() } else { None }
+            },
+            Topology::Toroidal => {
+                if width == 0 || height == 0 { return None; }
+                Point(point.0.rem_euclid(width), point.1.rem_euclid(height)).cast()
+            }
+        }
     }
 
     pub fn position_add(&self, pos:Position, delta_x:i32, delta_y:i32) -> Option<Position> {
-        let new_x:i32 = pos.0 as i32 + delta_x;
-        let new_y:i32 = pos.1 as i32 + delta_y;
-        if new_x < 0 || new_x >= self.width as i32 ||
-           new_y < 0 || new_y >= self.height as i32 {
-            return None;
-           }
-        Some((new_x as usize, new_y as usize))
+        self.to_position(pos.cast::<i64>()? + Vector(delta_x, delta_y).cast::<i64>()?)
+    }
+
+    // move {times} by {delta} in one go
+    pub fn move_by(&self, pos:Position, delta:Vector<i64>, times:i64) -> Option<Position> {
+        let pos = pos.cast::<i64>()?;
+        match self.topology {
+            Topology::Bounded => {
+                let x = pos.0.checked_add(delta.0.checked_mul(times)?)?;
+                let y = pos.1.checked_add(delta.1.checked_mul(times)?)?;
+                self.to_position(Point(x, y))
+            },
+            Topology::Toroidal => {
+                // only the remainders matter, so large counters do not overflow
+                let width = self.width as i64;
+                let height = self.height as i64;
+                if width == 0 || height == 0 { return None; }
+                let dx = delta.0.rem_euclid(width) * times.rem_euclid(width);
+                let dy = delta.1.rem_euclid(height) * times.rem_euclid(height);
+                self.to_position(pos + Vector(dx, dy))
+            }
+        }
     }
 
-    // return None if out of area
+    // return None if out of a bounded area
     pub fn step(&self, pos:Position, direction:Direction) -> Option<Position> {
-        let w = self.width-1;
-        let h = self.height-1;
-        match direction {
-            Up    => { if pos.1 > 0 { return Some(up   (pos)); } else { return None; }},
-            Right => { if pos.0 < w { return Some(right(pos)); } else { return None; }},
-            Down  => { if pos.1 < h { return Some(down (pos)); } else { return None; }},
-            Left  => { if pos.0 > 0 { return Some(left (pos)); } else { return None; }},
-            UpRight   => { if (pos.0 < w) & (pos.1 > 0) { return Some((pos.0+1,pos.1-1)); } else { return None; }},
-            DownRight => { if (pos.0 < h) & (pos.1 < w) { return Some((pos.0+1,pos.1+1)); } else { return None; }},
-            DownLeft  => { if (pos.0 > 0) & (pos.1 < w) { return Some((pos.0-1,pos.1+1)); } else { return None; }},
-            UpLeft    => { if (pos.0 > 0) & (pos.1 > 0) { return Some((pos.0-1,pos.1-1)); } else { return None; }}
+        if self.topology == Topology::Toroidal {
+            return self.move_by(pos, direction.delta(), 1);
         }
+        let delta = direction.delta::<isize>();
+        let x = pos.0.checked_add_signed(delta.0)?;
+        let y = pos.1.checked_add_signed(delta.1)?;
+        if x < self.width && y < self.height { Some(Point(x, y)) } else { None }
+    }
+
+    // the neighbours in the given directions which are inside the area
+    pub fn neighbors_in<'a, D:IntoIterator<Item=Direction>>(&'a self, pos:Position, directions:D) -> impl Iterator<Item=Position> + 'a where D::IntoIter: 'a {
+        directions.into_iter().filter_map(move |direction| self.step(pos, direction))
+    }
+
+    pub fn neighbors4(&self, pos:Position) -> impl Iterator<Item=Position> + '_ {
+        self.neighbors_in(pos, Direction::four_directions())
+    }
+
+    pub fn neighbors8(&self, pos:Position) -> impl Iterator<Item=Position> + '_ {
+        self.neighbors_in(pos, Direction::eight_directions())
+    }
+
+    // the start and the following positions in the direction up to the edge
+    // on a toroidal area it stops before coming back to the start
+    pub fn ray(&self, start:Position, direction:Direction) -> impl Iterator<Item=Position> + '_ {
+        std::iter::successors(Some(start), move |&pos| self.step(pos, direction).filter(|&next| next != start))
     }
 
     pub fn all_positions(&self) -> AreaIterator {
-        AreaIterator{area:&self, x:0,y:0, done:false}
+        AreaIterator{area:&self, x:0,y:0, done:self.width == 0 || self.height == 0}
     }
 }
 
 #[test]
 fn test_area() {
-    let area = Area{width:3,height:3};
-    assert_eq!(area.contains_signed(2,2), true);
-    assert_eq!(area.contains_signed(2,3), false);
-    assert_eq!(area.contains_signed(2,-1), false);
-    assert_eq!(area.step((2,2), Left), Some((1,2)));
-    assert_eq!(area.position_add((2,2), -1, -2), Some((1,0)));
-    assert_eq!(area.position_add((2,2),  1, -2), None);
+    let area = Area::new(3, 3);
+    assert_eq!(area.step(Point(2,2), Left), Some(Point(1,2)));
+    assert_eq!(area.position_add(Point(2,2), -1, -2), Some(Point(1,0)));
+    assert_eq!(area.position_add(Point(2,2),  1, -2), None);
+    assert_eq!(area.to_position(Point(0,2)), Some(Point(0,2)));
+    assert_eq!(area.to_position(Point(-1,2)), None);
     assert_eq!(area.all_positions().collect::<Vec<Position>>(), vec![
-        (0,0),(1,0),(2,0),
-        (0,1),(1,1),(2,1),
-        (0,2),(1,2),(2,2)
+        Point(0,0),Point(1,0),Point(2,0),
+        Point(0,1),Point(1,1),Point(2,1),
+        Point(0,2),Point(1,2),Point(2,2)
     ]);
+
+    // not square
+    let area = Area::new(2, 3);
+    assert_eq!(area.step(Point(0,1), DownRight), Some(Point(1,2)));
+    assert_eq!(area.step(Point(1,1), DownLeft), Some(Point(0,2)));
+    assert_eq!(area.step(Point(1,1), DownRight), None);
+    assert_eq!(area.step(Point(0,2), UpRight), Some(Point(1,1)));
+    assert_eq!(area.step(Point(0,2), DownRight), None);
+}
+
+#[test]
+fn test_toroidal() {
+    let area = Area::toroidal(4, 3);
+    assert_eq!(area.step(Point(0,0), Left), Some(Point(3,0)));
+    assert_eq!(area.step(Point(0,0), UpLeft), Some(Point(3,2)));
+    assert_eq!(area.step(Point(3,2), DownRight), Some(Point(0,0)));
+    assert_eq!(area.step(Point(1,1), Right), Some(Point(2,1)));
+    assert_eq!(area.position_add(Point(1,1), -6, 7), Some(Point(3,2)));
+    assert_eq!(area.to_position(Point(-1,-4)), Some(Point(3,2)));
+    assert_eq!(area.neighbors8(Point(0,0)).count(), 8);
+
+    assert_eq!(area.move_by(Point(1,1), Vector(2,-1), 0), Some(Point(1,1)));
+    assert_eq!(area.move_by(Point(1,1), Vector(2,-1), 3), Some(Point(3,1)));
+    assert_eq!(area.move_by(Point(1,1), Vector(2,-1), -1), Some(Point(3,2)));
+    // no overflow for large counters
+    assert_eq!(area.move_by(Point(1,1), Vector(-7,5), i64::MAX), area.move_by(Point(1,1), Vector(-7,5), i64::MAX % 12));
+
+    let bounded = Area::new(4, 3);
+    assert_eq!(bounded.move_by(Point(1,1), Vector(1,0), 2), Some(Point(3,1)));
+    assert_eq!(bounded.move_by(Point(1,1), Vector(1,0), 3), None);
+    assert_eq!(bounded.move_by(Point(1,1), Vector(1,0), i64::MAX), None);
+    assert_eq!(Area::toroidal(0, 3).step(Point(0,0), Up), None);
+}
+
+#[test]
+fn test_neighbors() {
+    let area = Area::new(3, 4);
+    assert_eq!(area.neighbors4(Point(1,1)).collect::<Vec<_>>(), vec![Point(2,1),Point(1,2),Point(0,1),Point(1,0)]);
+    assert_eq!(area.neighbors4(Point(0,0)).collect::<Vec<_>>(), vec![Point(1,0),Point(0,1)]);
+    assert_eq!(area.neighbors8(Point(1,1)).count(), 8);
+    assert_eq!(area.neighbors8(Point(2,3)).collect::<Vec<_>>(), vec![Point(1,3),Point(1,2),Point(2,2)]);
+    assert_eq!(area.neighbors_in(Point(0,3), [UpRight, DownRight, UpLeft]).collect::<Vec<_>>(), vec![Point(1,2)]);
+
+    // 1xN
+    let column = Area::new(1, 4);
+    assert_eq!(column.all_positions().count(), 4);
+    assert_eq!(column.neighbors8(Point(0,1)).collect::<Vec<_>>(), vec![Point(0,2),Point(0,0)]);
+    assert_eq!(column.neighbors4(Point(0,3)).collect::<Vec<_>>(), vec![Point(0,2)]);
+
+    // Nx1
+    let row = Area::new(4, 1);
+    assert_eq!(row.all_positions().collect::<Vec<_>>(), vec![Point(0,0),Point(1,0),Point(2,0),Point(3,0)]);
+    assert_eq!(row.neighbors8(Point(1,0)).collect::<Vec<_>>(), vec![Point(2,0),Point(0,0)]);
+
+    // 1x1 and empty
+    assert_eq!(Area::new(1, 1).neighbors8(Point(0,0)).count(), 0);
+    assert_eq!(Area::new(1, 1).all_positions().count(), 1);
+    for empty in [Area::new(0, 0), Area::new(0, 3), Area::new(3, 0)] {
+        assert_eq!(empty.all_positions().count(), 0);
+        assert_eq!(empty.neighbors8(Point(0,0)).count(), 0);
+    }
 }
 
 //////////////////////////////////////////
-/// EnumMap
+/// Rect
 //////////////////////////////////////////
 
+// a rectangle of positions, e.g. a part of an area
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Rect {
+    pub origin:Position, // upper left corner
+    pub width:usize,
+    pub height:usize
+}
+
+impl From<&Area> for Rect {
+    fn from(area:&Area) -> Rect {
+        Rect::new(Point(0, 0), area.width, area.height)
+    }
+}
+
+impl Rect {
+    pub fn new(origin:Position, width:usize, height:usize) -> Rect {
+        Rect { origin, width, height }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // the first position right of and below the rectangle
+    fn end(&self) -> Position {
+        Point(self.origin.0 + self.width, self.origin.1 + self.height)
+    }
+
+    pub fn contains(&self, pos:Position) -> bool {
+        (self.origin.0..self.end().0).contains(&pos.0) && (self.origin.1..self.end().1).contains(&pos.1)
+    }
+
+    pub fn contains_rect(&self, other:&Rect) -> bool {
+        other.is_empty() || (self.contains(other.origin) && other.end().0 <= self.end().0 && other.end().1 <= self.end().1)
+    }
+
+    // None if they do not overlap
+    #[allow(dead_code)]
+    pub fn intersection(&self, other:&Rect) -> Option<Rect> {
+        let origin = Point(self.origin.0.max(other.origin.0), self.origin.1.max(other.origin.1));
+        let end = Point(self.end().0.min(other.end().0), self.end().1.min(other.end().1));
+        if origin.0 < end.0 && origin.1 < end.1 { Some(Rect::new(origin, end.0 - origin.0, end.1 - origin.1)) } else { None }
+    }
+
+    // the smallest rectangle containing both
+    pub fn union(&self, other:&Rect) -> Rect {
+        if self.is_empty() { return *other; }
+        if other.is_empty() { return *self; }
+        let origin = Point(self.origin.0.min(other.origin.0), self.origin.1.min(other.origin.1));
+        let end = Point(self.end().0.max(other.end().0), self.end().1.max(other.end().1));
+        Rect::new(origin, end.0 - origin.0, end.1 - origin.1)
+    }
+
+    // upper left, upper right, lower left, lower right
+    // the middle row and column of an odd size belong to the lower and right quadrants
+    // unless they are excluded
+    pub fn quadrants(&self, exclude_centre:bool) -> [Rect;4] {
+        let (left, top) = (self.width / 2, self.height / 2);
+        let (right_start, bottom_start) = if exclude_centre { (self.width - left, self.height - top) } else { (left, top) };
+        let (right, bottom) = (self.width - right_start, self.height - bottom_start);
+        let Point(x, y) = self.origin;
+        [
+            Rect::new(Point(x, y), left, top),
+            Rect::new(Point(x + right_start, y), right, top),
+            Rect::new(Point(x, y + bottom_start), left, bottom),
+            Rect::new(Point(x + right_start, y + bottom_start), right, bottom)
+        ]
+    }
+
+    // in reading order
+    #[allow(dead_code)]
+    pub fn positions(&self) -> impl Iterator<Item=Position> {
+        let Rect { origin, width, height } = *self;
+        (0..height).flat_map(move |y| (0..width).map(move |x| Point(origin.0 + x, origin.1 + y)))
+    }
+
+    // a position inside of the rectangle to the position in its parent
+    pub fn to_parent(self, pos:Position) -> Position {
+        debug_assert!(pos.0 < self.width && pos.1 < self.height);
+        Point(self.origin.0 + pos.0, self.origin.1 + pos.1)
+    }
+
+    // a position in the parent to the position inside of the rectangle, None if outside
+    #[allow(dead_code)]
+    pub fn to_local(self, pos:Position) -> Option<Position> {
+        if self.contains(pos) { Some(Point(pos.0 - self.origin.0, pos.1 - self.origin.1)) } else { None }
+    }
+}
+
+#[test]
+fn test_rect() {
+    let area = Area::new(11, 7);
+    let rect = Rect::from(&area);
+    assert_eq!(rect, Rect::new(Point(0,0), 11, 7));
+    assert!(rect.contains(Point(10,6)));
+    assert!(!rect.contains(Point(11,6)));
+
+    let inner = Rect::new(Point(2,1), 3, 2);
+    assert!(rect.contains_rect(&inner));
+    assert!(!inner.contains_rect(&rect));
+    assert!(inner.contains_rect(&Rect::new(Point(20,20), 0, 0)));
+    assert_eq!(inner.positions().collect::<Vec<_>>(), [Point(2,1), Point(3,1), Point(4,1), Point(2,2), Point(3,2), Point(4,2)]);
+    assert_eq!(inner.to_parent(Point(1,1)), Point(3,2));
+    assert_eq!(inner.to_local(Point(3,2)), Some(Point(1,1)));
+    assert_eq!(inner.to_local(Point(1,2)), None);
+
+    let other = Rect::new(Point(4,2), 5, 5);
+    assert_eq!(inner.intersection(&other), Some(Rect::new(Point(4,2), 1, 1)));
+    assert_eq!(other.intersection(&inner), Some(Rect::new(Point(4,2), 1, 1)));
+    assert_eq!(inner.intersection(&Rect::new(Point(5,1), 2, 2)), None);
+    assert_eq!(inner.union(&other), Rect::new(Point(2,1), 7, 6));
+    assert_eq!(inner.union(&Rect::new(Point(0,0), 0, 0)), inner);
+
+    assert_eq!(rect.quadrants(true), [
+        Rect::new(Point(0,0), 5, 3), Rect::new(Point(6,0), 5, 3),
+        Rect::new(Point(0,4), 5, 3), Rect::new(Point(6,4), 5, 3)
+    ]);
+    assert_eq!(rect.quadrants(false), [
+        Rect::new(Point(0,0), 5, 3), Rect::new(Point(5,0), 6, 3),
+        Rect::new(Point(0,3), 5, 4), Rect::new(Point(5,3), 6, 4)
+    ]);
+    // nothing to exclude for even sizes
+    assert_eq!(inner.union(&other).quadrants(true), [
+        Rect::new(Point(2,1), 3, 3), Rect::new(Point(6,1), 3, 3),
+        Rect::new(Point(2,4), 3, 3), Rect::new(Point(6,4), 3, 3)
+    ]);
+    assert_eq!(rect.quadrants(false).iter().map(|quadrant| quadrant.positions().count()).sum::<usize>(), 11 * 7);
+}
+
+//////////////////////////////////////////
+/// EnumMap
+//////////////////////////////////////////
 pub trait FromChar {
     fn from_char(c:char) -> Self;
 }
 
+// implement this one, FromChar comes for free and panics on unknown characters
+pub trait TryFromChar : Sized {
+    fn try_from_char(c:char) -> Option<Self>;
+}
+
+impl<T:TryFromChar> FromChar for T {
+    fn from_char(c:char) -> Self {
+        T::try_from_char(c).unwrap_or_else(|| panic!("Unexpected character '{}' for {}", c, std::any::type_name::<T>()))
+    }
+}
+
 pub trait ToChar {
     fn to_char(self) -> char;
 }
@@ -175,118 +578,1625 @@ impl ToChar for char {
     }
 }
 
-#[derive(Clone)]
-pub struct PixelMap<E:FromChar> {
+// the pixels are stored row by row in one Vec
+// test_storage_timing compares this with one Vec per row on day 20's cost map and day 12's regions
+#[derive(Clone, PartialEq, Debug)]
+pub struct PixelMap<E> {
     pub area:Area,
-    pub pixels:Vec<Vec<E>>
+    pixels:Vec<E>
+}
+
+impl<E> PixelMap<E> {
+    fn index_of(&self, position:Position) -> usize {
+        // a too large x would silently address the next row
+        assert!(self.area.contains(position), "{:?} is outside of the map", position);
+        position.1 * self.area.width + position.0
+    }
+
+    pub fn row(&self, y:usize) -> &[E] {
+        &self.pixels[y * self.area.width..(y + 1) * self.area.width]
+    }
+
+    #[allow(dead_code)]
+    pub fn row_mut(&mut self, y:usize) -> &mut [E] {
+        let width = self.area.width;
+        &mut self.pixels[y * width..(y + 1) * width]
+    }
+
+    // the pixels of a column from top to bottom
+    #[allow(dead_code)]
+    pub fn column(&self, x:usize) -> impl Iterator<Item=&E> + '_ {
+        assert!(x < self.area.width, "column {} is outside of the map", x);
+        self.pixels[x..].iter().step_by(self.area.width)
+    }
+}
+
+impl<E> Index<Position> for PixelMap<E> {
+    type Output = E;
+    fn index(&self, position:Position) -> &E {
+        &self.pixels[self.index_of(position)]
+    }
 }
 
-impl<E:FromChar+Copy+PartialEq> PixelMap<E> {
+impl<E> IndexMut<Position> for PixelMap<E> {
+    fn index_mut(&mut self, position:Position) -> &mut E {
+        let index = self.index_of(position);
+        &mut self.pixels[index]
+    }
+}
+
+impl<E:Copy+PartialEq> PixelMap<E> {
 
     pub fn width(&self) -> usize { self.area.width }
 
     pub fn height(&self) -> usize { self.area.height }
 
     pub fn at(&self, position:Position) -> E {
-        self.pixels[position.1][position.0]
+        self[position]
     }
 
     pub fn set_at(&mut self, position:Position, value:E) {
-        self.pixels[position.1][position.0] = value;
+        self[position] = value;
     }
 
+    // None if the value is not in the map
+    #[allow(dead_code)]
     pub fn find_first(&self, value:E) -> Option<Position> {
-        for pos in self.area.all_positions() {
-            if self.at(pos) == value {
-                return Some(pos);
-            }
-        }
-        None
+        self.area.all_positions().find(|&pos| self.at(pos) == value)
+    }
+
+    // in reading order
+    pub fn find_all(&self, value:E) -> impl Iterator<Item=Position> + '_ {
+        self.area.all_positions().filter(move |&pos| self.at(pos) == value)
     }
 
     pub fn new(width:usize, height:usize, init_value:E) -> Self {
-        let mut pixels = Vec::new();
+        Self{area:Area::new(width, height), pixels:vec![init_value; width * height]}
+    }
 
-        for _y in 0..height {
-            let mut line = Vec::new();
-            for _x in 0..width {
-                line.push(init_value);
-            }
-            pixels.push(line);
-        }
-        Self{area:Area{width,height}, pixels}
+    // the value of each pixel is calculated from its position
+    pub fn from_fn(width:usize, height:usize, pixel_at:impl FnMut(Position) -> E) -> Self {
+        let area = Area::new(width, height);
+        let pixels = area.all_positions().map(pixel_at).collect();
+        Self{area, pixels}
     }
 
+}
+
+impl<E:TryFromChar+Copy+PartialEq> PixelMap<E> {
+    // panics on unknown characters or lines of different length
     pub fn from_strings<'a>(lines:impl Iterator<Item=&'a str>) -> Self {
+        Self::try_from_strings(lines).unwrap_or_else(|err| panic!("Could not create map, {}", err))
+    }
+
+    // all lines must have the width of the first line
+    pub fn try_from_strings<'a>(lines:impl Iterator<Item=&'a str>) -> Result<Self, InputError> {
         let mut width = 0;
         let mut height = 0;
         let mut pixels = Vec::new();
         for line in lines {
-            width = 0;
-            let mut row = Vec::new();
+            let mut line_width = 0;
             for c in line.chars() {
-                row.push(E::from_char(c));
-                width = width + 1;
+                let pixel = E::try_from_char(c).ok_or_else(|| InputError {
+                    line:height + 1, column:Some(line_width + 1), length:1,
+                    message:format!("unexpected character '{}' for {}", c, std::any::type_name::<E>())
+                })?;
+                pixels.push(pixel);
+                line_width += 1;
             }
-            pixels.push(row);
-            height = height + 1;
+            if height == 0 {
+                width = line_width;
+            } else if line_width != width {
+                return Err(InputError {
+                    line:height + 1, column:Some(width.min(line_width) + 1), length:1,
+                    message:format!("row has {} characters instead of {}", line_width, width)
+                });
+            }
+            height += 1;
+        }
+        Ok(Self{area:Area::new(width, height), pixels})
+    }
+}
+
+impl<E:ToChar+Copy+PartialEq> PixelMap<E> {
+    // markers like start and end must be in the map exactly once, they are replaced with background
+    // the map is only changed if all markers are found
+    // the lines and columns of the errors start with 1 like in the input
+    pub fn extract_markers<const N:usize>(&mut self, markers:[E;N], background:E) -> Result<[Position;N], InputError> {
+        let mut found:[Option<Position>;N] = [None;N];
+        for pos in self.area.all_positions() {
+            let Some(index) = markers.iter().position(|&marker| marker == self[pos]) else { continue };
+            if let Some(first) = found[index] {
+                return Err(InputError {
+                    line:pos.1 + 1, column:Some(pos.0 + 1), length:1,
+                    message:format!("'{}' is already at line {}, column {}", markers[index].to_char(), first.1 + 1, first.0 + 1)
+                });
+            }
+            found[index] = Some(pos);
+        }
+        if let Some(index) = found.iter().position(|pos| pos.is_none()) {
+            return Err(InputError::new(self.area.height.max(1), format!("there is no '{}' in the map", markers[index].to_char())));
         }
-        Self{area:Area{width,height}, pixels}
+        let positions = found.map(|pos| pos.unwrap());
+        for pos in positions {
+            self[pos] = background;
+        }
+        Ok(positions)
+    }
+}
+
+#[test]
+fn test_markers() {
+    let mut map = PixelMap::<char>::from_strings("#S.#\n#..E\n#S..".split('\n'));
+    assert_eq!(map.find_all('S').collect::<Vec<_>>(), [Point(1,0), Point(1,2)]);
+    assert_eq!(map.find_all('#').count(), 4);
+    assert_eq!(map.find_all('X').next(), None);
+    assert_eq!(map.find_first('S'), Some(Point(1,0)));
+    assert_eq!(map.find_first('E'), Some(Point(3,1)));
+    assert_eq!(map.find_first('X'), None);
+
+    let error = map.extract_markers(['E', 'S'], '.').unwrap_err();
+    assert_eq!(error.to_string(), "line 3, column 2: 'S' is already at line 1, column 2");
+    assert_eq!(map.extract_markers(['X'], '.').unwrap_err().to_string(), "line 3: there is no 'X' in the map");
+    // nothing was replaced
+    assert_eq!(map.find_all('S').count(), 2);
+
+    map.set_at(Point(1,2), '.');
+    assert_eq!(map.extract_markers(['S', 'E'], '.'), Ok([Point(1,0), Point(3,1)]));
+    assert_eq!(map.to_string(), "#..#\n#...\n#...");
+    assert_eq!(map.extract_markers([], '.'), Ok([]));
+}
+
+//////////////////////////////////////////
+/// Transformations
+//////////////////////////////////////////
+impl<E:Copy+PartialEq> PixelMap<E> {
+    // x and y swapped, i.e. mirrored at the diagonal from the upper left
+    #[allow(dead_code)]
+    pub fn transpose(&self) -> Self {
+        Self::from_fn(self.height(), self.width(), |pos| self[Point(pos.1, pos.0)])
+    }
+
+    // left becomes right
+    pub fn flip_horizontal(&self) -> Self {
+        let w = self.width();
+        Self::from_fn(w, self.height(), |pos| self[Point(w - 1 - pos.0, pos.1)])
+    }
+
+    // top becomes bottom
+    #[allow(dead_code)]
+    pub fn flip_vertical(&self) -> Self {
+        let h = self.height();
+        Self::from_fn(self.width(), h, |pos| self[Point(pos.0, h - 1 - pos.1)])
+    }
+
+    // 90 degrees clockwise, the left column becomes the top row
+    pub fn rotate_right(&self) -> Self {
+        let h = self.height();
+        Self::from_fn(h, self.width(), |pos| self[Point(pos.1, h - 1 - pos.0)])
+    }
+
+    // 90 degrees counterclockwise, the top row becomes the left column
+    #[allow(dead_code)]
+    pub fn rotate_left(&self) -> Self {
+        let w = self.width();
+        Self::from_fn(self.height(), w, |pos| self[Point(w - 1 - pos.1, pos.0)])
+    }
+
+    #[allow(dead_code)]
+    pub fn rotate_180(&self) -> Self {
+        let (w, h) = (self.width(), self.height());
+        Self::from_fn(w, h, |pos| self[Point(w - 1 - pos.0, h - 1 - pos.1)])
+    }
+
+    // the part of the map inside of {rect}
+    #[allow(dead_code)]
+    pub fn crop(&self, rect:&Rect) -> Self {
+        assert!(Rect::from(&self.area).contains_rect(rect),
+            "{}x{} at {:?} is not inside of the {}x{} map", rect.width, rect.height, rect.origin, self.width(), self.height());
+        Self::from_fn(rect.width, rect.height, |pos| self[rect.to_parent(pos)])
+    }
+
+    // each pixel becomes a block of factor_x * factor_y pixels
+    // {expand} gets the original pixel and the position inside of the block
+    pub fn scale(&self, factor_x:usize, factor_y:usize, expand:impl Fn(E, Position) -> E) -> Self {
+        Self::from_fn(self.width() * factor_x, self.height() * factor_y, |pos| {
+            let pixel = self[Point(pos.0 / factor_x, pos.1 / factor_y)];
+            expand(pixel, Point(pos.0 % factor_x, pos.1 % factor_y))
+        })
     }
+}
+
+#[test]
+fn test_transformations() {
+    type TestMap = PixelMap<char>;
+    let map = TestMap::from_strings(["abc", "def"].into_iter());
+    let lines = |map:&TestMap| (0..map.height()).map(|y| map.row(y).iter().collect::<String>()).collect::<Vec<_>>();
+
+    assert_eq!(lines(&map.flip_horizontal()), ["cba", "fed"]);
+    assert_eq!(map.flip_horizontal().flip_horizontal(), map);
+    assert_eq!(lines(&map.rotate_right()), ["da", "eb", "fc"]);
+    assert_eq!(map.rotate_right().area, Area::new(2, 3));
+
+    assert_eq!(lines(&map.scale(2, 1, |c, _| c)), ["aabbcc", "ddeeff"]);
+    assert_eq!(lines(&map.scale(1, 2, |c, offset| if offset.1 == 0 { c } else { c.to_ascii_uppercase() })), ["abc", "ABC", "def", "DEF"]);
+    assert_eq!(map.scale(3, 2, |c, _| c).area, Area::new(9, 4));
+}
+
+#[test]
+fn test_transpose() {
+    let map = PixelMap::<char>::from_strings(["abc", "def"].into_iter());
+    assert_eq!(map.transpose().to_string_lines(), ["ad", "be", "cf"]);
+    assert_eq!(map.transpose().area, Area::new(2, 3));
+    assert_eq!(map.transpose().transpose(), map);
+    assert_eq!(map.transpose(), map.rotate_right().flip_horizontal());
+}
+
+#[test]
+fn test_flip_vertical() {
+    let map = PixelMap::<char>::from_strings(["abc", "def", "ghi"].into_iter());
+    assert_eq!(map.flip_vertical().to_string_lines(), ["ghi", "def", "abc"]);
+    assert_eq!(map.flip_vertical().flip_vertical(), map);
+    assert_eq!(map.flip_vertical(), map.rotate_right().rotate_right().flip_horizontal());
+}
+
+#[test]
+fn test_rotate_left() {
+    let map = PixelMap::<char>::from_strings(["abc", "def"].into_iter());
+    assert_eq!(map.rotate_left().to_string_lines(), ["cf", "be", "ad"]);
+    assert_eq!(map.rotate_left().area, Area::new(2, 3));
+    assert_eq!(map.rotate_right().rotate_left(), map);
+    assert_eq!(map.rotate_left(), map.rotate_right().rotate_right().rotate_right());
+}
 
+#[test]
+fn test_rotate_180() {
+    let map = PixelMap::<char>::from_strings(["abc", "def"].into_iter());
+    assert_eq!(map.rotate_180().to_string_lines(), ["fed", "cba"]);
+    assert_eq!(map.rotate_180().rotate_180(), map);
+    assert_eq!(map.rotate_right().rotate_right(), map.rotate_180());
+}
+
+#[test]
+fn test_crop() {
+    let map = PixelMap::<char>::from_strings(["abc", "def"].into_iter());
+    assert_eq!(map.crop(&Rect::new(Point(1,0), 2, 2)).to_string_lines(), ["bc", "ef"]);
+    assert_eq!(map.crop(&Rect::new(Point(2,1), 1, 1)).to_string_lines(), ["f"]);
+    assert_eq!(map.crop(&Rect::from(&map.area)), map);
+    assert_eq!(map.crop(&Rect::new(Point(0,0), 0, 0)).area, Area::new(0, 0));
 }
 
-impl<E:FromChar+ToChar+Clone+Copy> PixelMap<E> {
+#[test]
+#[should_panic]
+fn test_crop_outside() {
+    PixelMap::<char>::new(3, 2, '.').crop(&Rect::new(Point(2,0), 2, 1));
+}
+
+//////////////////////////////////////////
+/// Text
+//////////////////////////////////////////
+impl<E:ToChar+Clone+Copy> PixelMap<E> {
     pub fn println(&self) {
+        self.write_to(std::io::stdout().lock()).expect("Could not write to stdout");
+    }
+
+    pub fn to_string_lines(&self) -> Vec<String> {
+        (0..self.area.height).map(|y| self.row(y).iter().map(|pixel| pixel.to_char()).collect()).collect()
+    }
+
+    // every row ends with a newline, e.g. for a file
+    pub fn write_to(&self, mut writer:impl Write) -> std::io::Result<()> {
+        for line in self.to_string_lines() {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// the rows separated by newlines, without a newline after the last row
+impl<E:ToChar+Clone+Copy> fmt::Display for PixelMap<E> {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
         for y in 0..self.area.height {
-            let line = &self.pixels[y];
-            for x in 0..self.area.width {
-                print!("{}", line[x].to_char());
+            if y > 0 { writeln!(f)?; }
+            for pixel in self.row(y) {
+                write!(f, "{}", pixel.to_char())?;
             }
-            println!("");
         }
+        Ok(())
+    }
+}
+
+impl<E:TryFromChar+Copy+PartialEq> FromStr for PixelMap<E> {
+    type Err = InputError;
+    fn from_str(text:&str) -> Result<Self, InputError> {
+        Self::try_from_strings(text.lines())
     }
 }
 
+//////////////////////////////////////////
+/// Diff
+//////////////////////////////////////////
+
 #[derive(PartialEq, Debug, Clone, Copy)]
-enum TestEnum {
-    A,
-    B,
-    C
+pub struct Change<E> {
+    pub position:Position,
+    pub old:E, // in self
+    pub new:E  // in other
 }
 
-impl FromChar for char {
-    fn from_char(c:char) -> Self { c }
+impl<E:Copy+PartialEq> PixelMap<E> {
+    // the pixels which are different in other, in reading order
+    // both maps must have the same size
+    pub fn diff(&self, other:&PixelMap<E>) -> Vec<Change<E>> {
+        assert_eq!((self.width(), self.height()), (other.width(), other.height()), "Only maps of the same size can be compared");
+        self.area.all_positions()
+            .filter(|&position| self[position] != other[position])
+            .map(|position| Change { position, old:self[position], new:other[position] })
+            .collect()
+    }
 }
 
-impl FromChar for TestEnum {
-    fn from_char(c:char) -> Self {
-        match c {
-            'A' => TestEnum::A,
-            'B' => TestEnum::B,
-            'C' => TestEnum::C,
-            _ => panic!("Unexpected character {} for TestEnum", c)
+impl<E:ToChar+Copy+PartialEq> PixelMap<E> {
+    // both maps side by side and a third map with an 'X' for every changed pixel, e.g.
+    //   2 pixels differ
+    //   old    new    changes
+    //   #.O    #O.    .XX
+    #[allow(dead_code)]
+    pub fn render_diff(&self, other:&PixelMap<E>) -> String {
+        let changes = self.diff(other);
+        let mut marks = PixelMap::new(self.area.width, self.area.height, '.');
+        for change in &changes {
+            marks[change.position] = 'X';
         }
+        let width = self.area.width.max("changes".len());
+        let mut lines = vec![
+            match changes.len() {
+                1 => "1 pixel differs".to_string(),
+                count => format!("{} pixels differ", count)
+            },
+            format!("{:<width$}  {:<width$}  changes", "old", "new")
+        ];
+        for ((old, new), changed) in self.to_string_lines().iter().zip(other.to_string_lines()).zip(marks.to_string_lines()) {
+            lines.push(format!("{:<width$}  {:<width$}  {}", old, new, changed));
+        }
+        lines.join("\n")
+    }
+}
+
+// like assert_eq!, but the message shows where the maps differ
+#[cfg(test)]
+#[track_caller]
+pub fn assert_maps_eq<E:ToChar+Copy+PartialEq>(left:&PixelMap<E>, right:&PixelMap<E>) {
+    if (left.width(), left.height()) != (right.width(), right.height()) {
+        panic!("The maps have different sizes {}x{} and {}x{}\n{}\n\n{}", left.width(), left.height(), right.width(), right.height(), left, right);
+    }
+    if left != right {
+        panic!("The maps are different\n{}", left.render_diff(right));
     }
 }
 
 #[test]
-fn test_from_strings() {
-    type TestMap = PixelMap<TestEnum>;
-    let input =
-"ABC
-BCA";
-    let pixel_map = TestMap::from_strings(input.split('\n'));
-    assert_eq!(pixel_map.area, Area{width:3, height:2});
-    assert_eq!(pixel_map.width(), 3);
-    assert_eq!(pixel_map.height(), 2);
-    assert_eq!(pixel_map.pixels, vec![
-        vec![TestEnum::A, TestEnum::B, TestEnum::C],
-        vec![TestEnum::B, TestEnum::C, TestEnum::A]
-    ]);
-    assert_eq!(pixel_map.at((1,1)), TestEnum::C);
-    assert_eq!(pixel_map.area.all_positions().map( |pos| pixel_map.at(pos) ).collect::<Vec<_>>(), vec![
-        TestEnum::A, TestEnum::B, TestEnum::C,
-        TestEnum::B, TestEnum::C, TestEnum::A
+fn test_diff() {
+    let before = PixelMap::<char>::from_strings("#.O.\n#..O".split('\n'));
+    let after = PixelMap::<char>::from_strings("#O..\n#..O".split('\n'));
+    assert_eq!(before.diff(&after), [
+        Change { position:Point(1,0), old:'.', new:'O' },
+        Change { position:Point(2,0), old:'O', new:'.' }
     ]);
-}
\ No newline at end of file
+    assert_eq!(before.diff(&before), []);
+    assert_eq!(before.render_diff(&after),
+"2 pixels differ
+old      new      changes
+#.O.     #O..     .XX.
+#..O     #..O     ....");
+    assert!(before.render_diff(&PixelMap::<char>::from_strings("#.O.\n#...".split('\n'))).starts_with("1 pixel differs\n"));
+    assert_maps_eq(&after, &after);
+    let message = std::panic::catch_unwind(|| assert_maps_eq(&before, &after)).unwrap_err();
+    assert!(message.downcast_ref::<String>().unwrap().ends_with(".XX.\n#..O     #..O     ...."));
+}
+
+#[test]
+#[should_panic(expected = "different sizes 4x2 and 3x2")]
+fn test_diff_size() {
+    assert_maps_eq(&PixelMap::new(4, 2, '.'), &PixelMap::new(3, 2, '.'));
+}
+
+//////////////////////////////////////////
+/// Grid
+//////////////////////////////////////////
+// common access to dense and sparse maps
+// positions outside of the bounds panic
+pub trait Grid {
+    type Element:Copy;
+    // None for an unbounded grid
+    fn bounds(&self) -> Option<&Area>;
+    // None for an empty cell, a PixelMap has none
+    fn get(&self, position:Position) -> Option<Self::Element>;
+    fn set(&mut self, position:Position, value:Self::Element);
+    // every pixel of a PixelMap is occupied
+    fn occupied(&self) -> impl Iterator<Item=(Position, Self::Element)> + '_;
+}
+
+impl<E:Copy+PartialEq> Grid for PixelMap<E> {
+    type Element = E;
+
+    fn bounds(&self) -> Option<&Area> {
+        Some(&self.area)
+    }
+
+    fn get(&self, position:Position) -> Option<E> {
+        Some(self[position])
+    }
+
+    fn set(&mut self, position:Position, value:E) {
+        self[position] = value;
+    }
+
+    fn occupied(&self) -> impl Iterator<Item=(Position, E)> + '_ {
+        self.area.all_positions().map(|position| (position, self[position]))
+    }
+}
+
+// only the occupied cells are stored, the rest is empty
+#[derive(Clone, PartialEq, Debug)]
+pub struct SparseMap<E> {
+    pub area:Option<Area>,
+    cells:HashMap<Position, E>
+}
+
+impl<E:Copy> SparseMap<E> {
+    pub fn new(area:Area) -> Self {
+        SparseMap { area:Some(area), cells:HashMap::new() }
+    }
+
+    // any position can be set
+    #[allow(dead_code)]
+    pub fn unbounded() -> Self {
+        SparseMap { area:None, cells:HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    // the cell is empty afterwards
+    #[allow(dead_code)]
+    pub fn remove(&mut self, position:Position) -> Option<E> {
+        self.check(position);
+        self.cells.remove(&position)
+    }
+
+    fn check(&self, position:Position) {
+        if let Some(area) = &self.area {
+            assert!(area.contains(position), "Position ({},{}) is outside of the map", position.0, position.1);
+        }
+    }
+}
+
+impl<E:Copy> Grid for SparseMap<E> {
+    type Element = E;
+
+    fn bounds(&self) -> Option<&Area> {
+        self.area.as_ref()
+    }
+
+    fn get(&self, position:Position) -> Option<E> {
+        self.check(position);
+        self.cells.get(&position).copied()
+    }
+
+    fn set(&mut self, position:Position, value:E) {
+        self.check(position);
+        self.cells.insert(position, value);
+    }
+
+    fn occupied(&self) -> impl Iterator<Item=(Position, E)> + '_ {
+        self.cells.iter().map(|(&position, &value)| (position, value))
+    }
+}
+
+#[test]
+fn test_grid() {
+    // written once for both representations
+    fn draw_diagonal<G:Grid<Element=char>>(grid:&mut G, length:usize) {
+        for i in 0..length {
+            grid.set(Point(i, i), '#');
+        }
+    }
+    fn count<G:Grid<Element=char>>(grid:&G, c:char) -> usize {
+        grid.occupied().filter(|&(_, value)| value == c).count()
+    }
+
+    let mut pixels = PixelMap::new(3, 2, '.');
+    draw_diagonal(&mut pixels, 2);
+    assert_eq!(pixels.to_string(), "#..\n.#.");
+    assert_eq!(count(&pixels, '#'), 2);
+    assert_eq!(pixels.occupied().count(), 6);
+    assert_eq!(pixels.get(Point(1,1)), Some('#'));
+    assert_eq!(pixels.get(Point(2,1)), Some('.'));
+
+    let mut sparse = SparseMap::new(Area::new(3, 2));
+    draw_diagonal(&mut sparse, 2);
+    assert_eq!(count(&sparse, '#'), 2);
+    assert_eq!(sparse.len(), 2);
+    assert_eq!(sparse.get(Point(1,1)), Some('#'));
+    assert_eq!(sparse.get(Point(2,1)), None);
+    assert_eq!(sparse.bounds(), Some(&Area::new(3, 2)));
+    let mut occupied = sparse.occupied().collect::<Vec<_>>();
+    occupied.sort();
+    assert_eq!(occupied, [(Point(0,0), '#'), (Point(1,1), '#')]);
+    assert_eq!(sparse.remove(Point(0,0)), Some('#'));
+    assert_eq!(sparse.remove(Point(2,1)), None);
+    assert_eq!(sparse.occupied().collect::<Vec<_>>(), [(Point(1,1), '#')]);
+
+    let mut unbounded = SparseMap::unbounded();
+    draw_diagonal(&mut unbounded, 1000);
+    assert_eq!(unbounded.len(), 1000);
+    assert_eq!(unbounded.get(Point(999,999)), Some('#'));
+    assert_eq!(unbounded.get(Point(1000,0)), None);
+    assert_eq!(unbounded.bounds(), None);
+}
+
+#[test]
+#[should_panic]
+fn test_sparse_outside() {
+    SparseMap::new(Area::new(3, 2)).set(Point(0,2), true);
+}
+
+#[test]
+#[should_panic(expected = "Position (3,0) is outside of the map")]
+fn test_sparse_get_outside() {
+    // not just an empty cell
+    SparseMap::<bool>::new(Area::new(3, 2)).get(Point(3,0));
+}
+
+#[test]
+#[should_panic(expected = "outside of the map")]
+fn test_pixels_get_outside() {
+    PixelMap::new(3, 2, '.').get(Point(0,2));
+}
+
+//////////////////////////////////////////
+/// Components
+//////////////////////////////////////////
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Connectivity {
+    Four, // only horizontal and vertical neighbours are connected
+    #[allow(dead_code)]
+    Eight // diagonal neighbours are connected as well
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Component {
+    pub cells:Vec<Position>,
+    pub area:usize, // = number of cells
+    pub perimeter:usize, // = number of cell edges to other components or the border
+    pub sides:usize, // = number of straight sides of the outline, the same as its corners
+    pub bounding_box:Rect
+}
+
+// label every pixel with the index of its component, starting with 0 in reading order
+// a neighbour joins the component if same(current pixel, neighbour) is true
+pub fn components<E:Copy>(map:&PixelMap<E>, connectivity:Connectivity, same:impl Fn(E, E) -> bool) -> (PixelMap<usize>, Vec<Component>) {
+    const UNLABELLED:usize = usize::MAX;
+    let mut labels = PixelMap::new(map.area.width, map.area.height, UNLABELLED);
+    let mut components = Vec::new();
+    for start in map.area.all_positions() {
+        if labels[start] != UNLABELLED { continue; }
+        let label = components.len();
+        labels[start] = label;
+        let mut cells = vec![start];
+        let mut backlog = vec![start];
+        while let Some(pos) = backlog.pop() {
+            let neighbors:Vec<Position> = match connectivity {
+                Connectivity::Four => map.area.neighbors4(pos).collect(),
+                Connectivity::Eight => map.area.neighbors8(pos).collect()
+            };
+            for next in neighbors {
+                if labels[next] == UNLABELLED && same(map[pos], map[next]) {
+                    labels[next] = label;
+                    cells.push(next);
+                    backlog.push(next);
+                }
+            }
+        }
+        cells.sort_by_key(|pos| (pos.1, pos.0));
+        components.push(describe_component(&labels, label, cells));
+    }
+    (labels, components)
+}
+
+fn describe_component(labels:&PixelMap<usize>, label:usize, cells:Vec<Position>) -> Component {
+    let member = |pos:Position, direction:Direction| labels.area.neighbors_in(pos, [direction]).any(|next| labels[next] == label);
+    let directions = Direction::eight_directions();
+    let mut perimeter = 0;
+    let mut sides = 0;
+    for &pos in &cells {
+        perimeter += Direction::four_directions().iter().filter(|&&direction| !member(pos, direction)).count();
+        // every corner of the outline starts a new side
+        // the diagonal between two sides is every second of the eight directions
+        for side in 0..4 {
+            let first = member(pos, directions[2 * side]);
+            let second = member(pos, directions[(2 * side + 2) % 8]);
+            let diagonal = member(pos, directions[2 * side + 1]);
+            if (!first && !second) || (first && second && !diagonal) {
+                sides += 1;
+            }
+        }
+    }
+    let bounding_box = cells.iter().fold(Rect::new(cells[0], 0, 0), |rect, &pos| rect.union(&Rect::new(pos, 1, 1)));
+    Component { area:cells.len(), cells, perimeter, sides, bounding_box }
+}
+
+// the closed outlines of a component through the corners of its cells, with the component on the right
+// corner (x,y) is the upper left corner of pixel (x,y), only the corners where the outline turns are listed
+// the number of corners of all outlines is the number of sides, every hole has an outline of its own
+pub fn outlines(labels:&PixelMap<usize>, label:usize) -> Vec<Vec<Position>> {
+    let member = |pos:Position, direction:Direction| labels.area.neighbors_in(pos, [direction]).any(|next| labels[next] == label);
+    // the edges that start at each corner
+    let mut edges:HashMap<Position, Vec<Direction>> = HashMap::new();
+    for pos in labels.area.all_positions().filter(|&pos| labels[pos] == label) {
+        let Point(x, y) = pos;
+        for (direction, side, corner) in [(Right, Up, Point(x, y)), (Down, Right, Point(x+1, y)), (Left, Down, Point(x+1, y+1)), (Up, Left, Point(x, y+1))] {
+            if !member(pos, side) {
+                edges.entry(corner).or_default().push(direction);
+            }
+        }
+    }
+    let step = |corner:Position, direction:Direction| -> Position {
+        let Vector(dx, dy) = direction.delta::<i64>();
+        Point((corner.0 as i64 + dx) as usize, (corner.1 as i64 + dy) as usize)
+    };
+    let mut outlines = Vec::new();
+    while let Some(&start) = edges.keys().min_by_key(|corner| (corner.1, corner.0)) {
+        let mut walked:Vec<(Position, Direction)> = Vec::new();
+        let mut corner = start;
+        let mut direction = edges[&start][0];
+        // where two outlines touch diagonally, turning right keeps them apart
+        while let Some(outgoing) = edges.get_mut(&corner) {
+            let next = [direction.turn_right(), direction, direction.turn_left()].into_iter()
+                .find(|candidate| outgoing.contains(candidate))
+                .unwrap();
+            outgoing.retain(|&edge| edge != next);
+            if outgoing.is_empty() { edges.remove(&corner); }
+            walked.push((corner, next));
+            direction = next;
+            corner = step(corner, next);
+        }
+        let turns = (0..walked.len())
+            .filter(|&i| walked[i].1 != walked[(i + walked.len() - 1) % walked.len()].1)
+            .map(|i| walked[i].0)
+            .collect();
+        outlines.push(turns);
+    }
+    outlines
+}
+
+#[test]
+fn test_components() {
+    let map = PixelMap::<char>::from_strings(
+"AAB.
+A.B.
+.B..
+BB.C".split('\n'));
+
+    let (labels, four) = components(&map, Connectivity::Four, |a, b| a == b);
+    assert_eq!(four.len(), 7);
+    assert_eq!(labels.row(0), [0, 0, 1, 2]);
+    assert_eq!(labels.row(3), [5, 5, 2, 6]);
+    assert_eq!(four[0], Component {
+        cells:vec![Point(0,0), Point(1,0), Point(0,1)],
+        area:3, perimeter:8, sides:6,
+        bounding_box:Rect::new(Point(0,0), 2, 2)
+    });
+    assert_eq!((four[1].perimeter, four[1].sides), (6, 4));
+    assert_eq!((four[2].area, four[2].perimeter, four[2].sides), (5, 12, 8));
+    assert_eq!(four[2].bounding_box, Rect::new(Point(2,0), 2, 4));
+    // the dots at (1,1) and (0,2) are enclosed
+    assert_eq!((four[3].area, four[4].area), (1, 1));
+
+    let (labels, eight) = components(&map, Connectivity::Eight, |a, b| a == b);
+    assert_eq!(eight.len(), 4);
+    assert_eq!(labels.row(3), [1, 1, 2, 3]);
+    // the Bs touch diagonally at (2,1) and (1,2)
+    assert_eq!(eight[1].cells, [Point(2,0), Point(2,1), Point(1,2), Point(0,3), Point(1,3)]);
+    assert_eq!(eight[1].bounding_box, Rect::new(Point(0,0), 3, 4));
+    assert_eq!((eight[1].perimeter, eight[1].sides), (14, 10));
+    // now all dots are connected
+    assert_eq!(eight[2].area, 7);
+
+    // regions that allow steps of 1
+    let heights = PixelMap::<char>::from_strings("0129\n9939".split('\n'));
+    let (_, regions) = components(&heights, Connectivity::Four, |a, b| a.max(b) as u32 - a.min(b) as u32 <= 1);
+    assert_eq!(regions.iter().map(|region| region.area).collect::<Vec<_>>(), [4, 2, 2]);
+    assert_eq!(components(&PixelMap::new(0, 0, 'A'), Connectivity::Four, |a, b| a == b).1, []);
+}
+
+#[test]
+fn test_outlines() {
+    let map = PixelMap::<char>::from_strings(
+"AAB.
+A.B.
+.B..
+BB.C".split('\n'));
+    for connectivity in [Connectivity::Four, Connectivity::Eight] {
+        let (labels, components) = components(&map, connectivity, |a, b| a == b);
+        for (label, component) in components.iter().enumerate() {
+            let corners:usize = outlines(&labels, label).iter().map(|outline| outline.len()).sum();
+            assert_eq!(corners, component.sides);
+        }
+        assert_eq!(outlines(&labels, 0), [vec![Point(0,0), Point(2,0), Point(2,1), Point(1,1), Point(1,2), Point(0,2)]]);
+    }
+    // the Bs touch diagonally at corner (2,2), which is a corner of both of their outlines
+    let (labels, _) = components(&map, Connectivity::Eight, |a, b| a == b);
+    assert_eq!(outlines(&labels, 1), [
+        vec![Point(2,0), Point(3,0), Point(3,2), Point(2,2)],
+        vec![Point(1,2), Point(2,2), Point(2,4), Point(0,4), Point(0,3), Point(1,3)]
+    ]);
+
+    // one outline for the hole
+    let ring = PixelMap::<char>::from_strings("OOO\nOXO\nOOO".split('\n'));
+    let (labels, _) = components(&ring, Connectivity::Four, |a, b| a == b);
+    assert_eq!(outlines(&labels, 0), [
+        vec![Point(0,0), Point(3,0), Point(3,3), Point(0,3)],
+        vec![Point(1,1), Point(1,2), Point(2,2), Point(2,1)]
+    ]);
+    assert_eq!(outlines(&labels, 1), [vec![Point(1,1), Point(2,1), Point(2,2), Point(1,2)]]);
+}
+
+//////////////////////////////////////////
+/// Distance field
+//////////////////////////////////////////
+pub struct DistanceField {
+    pub distances:PixelMap<Option<u32>>, // None if not reached
+    pub predecessors:PixelMap<Option<Direction>>, // the direction of the last step, None for the sources
+    pub reached:Option<Position> // the target that stopped the search
+}
+
+impl DistanceField {
+    // the positions from a source to the target, None if not reached
+    pub fn path_to(&self, target:Position) -> Option<Vec<Position>> {
+        self.distances[target]?;
+        let mut path = vec![target];
+        let mut pos = target;
+        while let Some(direction) = self.predecessors[pos] {
+            pos = self.distances.area.step(pos, direction.turn_right().turn_right()).unwrap();
+            path.push(pos);
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// breadth first search with unit costs from all sources
+// passable(from, to) decides whether one step between two neighbours is possible
+pub fn distance_field<G:Grid>(grid:&G, sources:impl IntoIterator<Item=Position>, passable:impl Fn(Position, Position) -> bool) -> DistanceField {
+    distance_field_to(grid, sources, passable, |_| false)
+}
+
+// the same, but stop as soon as a target is reached
+pub fn distance_field_to<G:Grid>(grid:&G, sources:impl IntoIterator<Item=Position>, passable:impl Fn(Position, Position) -> bool, is_target:impl Fn(Position) -> bool) -> DistanceField {
+    let area = grid.bounds().expect("A distance field needs a bounded grid");
+    let mut field = DistanceField {
+        distances:PixelMap::new(area.width, area.height, None),
+        predecessors:PixelMap::new(area.width, area.height, None),
+        reached:None
+    };
+    // keep the topology for path_to
+    field.distances.area = area.clone();
+    let mut backlog = VecDeque::new();
+    for source in sources {
+        if field.distances[source].is_some() { continue; }
+        field.distances[source] = Some(0);
+        if is_target(source) {
+            field.reached = Some(source);
+            return field;
+        }
+        backlog.push_back(source);
+    }
+    while let Some(pos) = backlog.pop_front() {
+        let distance = field.distances[pos].unwrap() + 1;
+        for direction in Direction::four_directions() {
+            let Some(next) = area.step(pos, direction) else { continue; };
+            if field.distances[next].is_some() || !passable(pos, next) { continue; }
+            field.distances[next] = Some(distance);
+            field.predecessors[next] = Some(direction);
+            if is_target(next) {
+                field.reached = Some(next);
+                return field;
+            }
+            backlog.push_back(next);
+        }
+    }
+    field
+}
+
+#[test]
+fn test_distance_field() {
+    let map = PixelMap::<char>::from_strings(
+"S..#
+.#.#
+.#..
+##.#".split('\n'));
+    let open = |_, to| map[to] != '#';
+
+    let field = distance_field(&map, [Point(0,0)], open);
+    assert_eq!(field.distances.row(0), [Some(0), Some(1), Some(2), None]);
+    assert_eq!(field.distances.row(3), [None, None, Some(5), None]);
+    assert_eq!(field.distances[Point(0,2)], Some(2));
+    assert_eq!(field.reached, None);
+    assert_eq!(field.predecessors[Point(2,1)], Some(Down));
+    assert_eq!(field.path_to(Point(3,2)).unwrap(), [Point(0,0), Point(1,0), Point(2,0), Point(2,1), Point(2,2), Point(3,2)]);
+    assert_eq!(field.path_to(Point(0,0)).unwrap(), [Point(0,0)]);
+    assert_eq!(field.path_to(Point(1,1)), None);
+
+    // two sources, stop at the first target
+    let field = distance_field_to(&map, [Point(0,0), Point(2,3)], open, |pos| pos == Point(2,1));
+    assert_eq!(field.reached, Some(Point(2,1)));
+    assert_eq!(field.distances[Point(2,1)], Some(2));
+    assert_eq!(field.path_to(Point(2,1)).unwrap()[0], Point(2,3));
+
+    // only downhill
+    let heights = PixelMap::<char>::from_strings("321\n432".split('\n'));
+    let field = distance_field(&heights, [Point(0,1)], |from, to| heights[to] < heights[from]);
+    assert_eq!(field.distances.row(0), [Some(1), Some(2), Some(3)]);
+    assert_eq!(field.distances.row(1), [Some(0), Some(1), Some(2)]);
+
+    // sparse maps work as well
+    let mut walls = SparseMap::new(Area::new(3, 1));
+    walls.set(Point(1,0), true);
+    let field = distance_field(&walls, [Point(0,0)], |_, to| walls.get(to).is_none());
+    assert_eq!(field.distances.row(0), [Some(0), None, None]);
+}
+
+//////////////////////////////////////////
+/// Junction graph
+//////////////////////////////////////////
+
+// a corridor leads from one node to the next without passing another node
+#[derive(PartialEq, Debug, Clone)]
+pub struct Corridor {
+    pub from:usize,
+    pub to:usize,
+    pub length:usize, // = number of steps
+    pub turns:usize, // inside the corridor, not at its nodes
+    pub first:Direction, // of the first step
+    pub last:Direction, // of the last step
+    pub cells:Vec<Position> // after from up to and including to
+}
+
+// a maze of mostly one wide corridors as a weighted graph
+// the nodes are junctions, dead ends and marked cells, every corridor is there in both directions
+// cells on a closed loop without any node are not part of the graph
+pub struct JunctionGraph {
+    pub nodes:Vec<Position>, // in reading order
+    pub corridors:Vec<Corridor>,
+    node_at:PixelMap<Option<usize>>,
+    outgoing:Vec<Vec<usize>> // the corridors starting at each node
+}
+
+pub fn junction_graph<E:Copy>(map:&PixelMap<E>, passable:impl Fn(E) -> bool, marked:impl IntoIterator<Item=Position>) -> JunctionGraph {
+    let area = &map.area;
+    let is_free = |pos:Position| passable(map[pos]);
+    let mut node_at = PixelMap::new(area.width, area.height, false);
+    for pos in area.all_positions().filter(|&pos| is_free(pos)) {
+        node_at[pos] = area.neighbors4(pos).filter(|&next| is_free(next)).count() != 2;
+    }
+    for pos in marked {
+        assert!(is_free(pos), "The marked position ({},{}) is not passable", pos.0, pos.1);
+        node_at[pos] = true;
+    }
+    let nodes:Vec<Position> = node_at.find_all(true).collect();
+    let mut graph = JunctionGraph {
+        node_at:PixelMap::new(area.width, area.height, None),
+        outgoing:vec![Vec::new(); nodes.len()],
+        corridors:Vec::new(),
+        nodes
+    };
+    for (index, &node) in graph.nodes.iter().enumerate() {
+        graph.node_at[node] = Some(index);
+    }
+    for from in 0..graph.nodes.len() {
+        for first in Direction::four_directions() {
+            let Some(mut pos) = area.step(graph.nodes[from], first).filter(|&pos| is_free(pos)) else { continue };
+            let mut corridor = Corridor { from, to:from, length:1, turns:0, first, last:first, cells:vec![pos] };
+            // every cell in between has exactly two free neighbours, one of them is behind
+            while graph.node_at[pos].is_none() {
+                let (direction, next) = [corridor.last, corridor.last.turn_left(), corridor.last.turn_right()].into_iter()
+                    .filter_map(|direction| area.step(pos, direction).filter(|&next| is_free(next)).map(|next| (direction, next)))
+                    .next()
+                    .unwrap();
+                if direction != corridor.last { corridor.turns += 1; }
+                corridor.last = direction;
+                corridor.length += 1;
+                corridor.cells.push(next);
+                pos = next;
+            }
+            corridor.to = graph.node_at[pos].unwrap();
+            graph.outgoing[from].push(graph.corridors.len());
+            graph.corridors.push(corridor);
+        }
+    }
+    graph
+}
+
+impl JunctionGraph {
+    pub fn node_at(&self, pos:Position) -> Option<usize> {
+        self.node_at[pos]
+    }
+
+    // all cells of a path of corridors, starting with its first node
+    pub fn positions(&self, path:&[usize]) -> Vec<Position> {
+        let Some(&first) = path.first() else { return Vec::new() };
+        let mut positions = vec![self.nodes[self.corridors[first].from]];
+        for pair in path.windows(2) {
+            assert_eq!(self.corridors[pair[0]].to, self.corridors[pair[1]].from, "The corridors {} and {} are not connected", pair[0], pair[1]);
+        }
+        for &corridor in path {
+            positions.extend_from_slice(&self.corridors[corridor].cells);
+        }
+        positions
+    }
+
+    // Dijkstra over the corridors
+    // cost(previous, next) is the cost of taking corridor next after previous, which is None at the start
+    // so that turns at the nodes can be paid
+    pub fn cheapest_paths(&self, start:usize, cost:impl Fn(Option<&Corridor>, &Corridor) -> u32) -> CorridorPaths {
+        let mut paths = CorridorPaths {
+            start,
+            costs:vec![None; self.corridors.len()],
+            predecessors:vec![Vec::new(); self.corridors.len()]
+        };
+        let mut backlog = BinaryHeap::new();
+        for &next in &self.outgoing[start] {
+            backlog.push(Reverse((cost(None, &self.corridors[next]), next, None)));
+        }
+        while let Some(Reverse((total, corridor, previous))) = backlog.pop() {
+            match paths.costs[corridor] {
+                Some(known) if known < total => { continue; },
+                Some(_) => {
+                    // another path which is just as cheap
+                    paths.predecessors[corridor].extend(previous);
+                    continue;
+                },
+                None => {}
+            }
+            paths.costs[corridor] = Some(total);
+            paths.predecessors[corridor].extend(previous);
+            let current = &self.corridors[corridor];
+            for &next in &self.outgoing[current.to] {
+                let next_total = total + cost(Some(current), &self.corridors[next]);
+                // just as cheap is needed for the predecessors
+                if paths.costs[next].is_none_or(|known| next_total <= known) {
+                    backlog.push(Reverse((next_total, next, Some(corridor))));
+                }
+            }
+        }
+        paths
+    }
+}
+
+pub struct CorridorPaths {
+    start:usize,
+    pub costs:Vec<Option<u32>>, // of the cheapest path up to the end of each corridor, None if not reached
+    predecessors:Vec<Vec<usize>> // all corridors before each corridor on a cheapest path
+}
+
+impl CorridorPaths {
+    // the cheapest way into the node
+    fn arrivals(&self, graph:&JunctionGraph, node:usize) -> (Option<u32>, Vec<usize>) {
+        if node == self.start { return (Some(0), Vec::new()); }
+        let arriving:Vec<usize> = (0..graph.corridors.len()).filter(|&corridor| graph.corridors[corridor].to == node && self.costs[corridor].is_some()).collect();
+        let cost = arriving.iter().filter_map(|&corridor| self.costs[corridor]).min();
+        (cost, arriving.into_iter().filter(|&corridor| self.costs[corridor] == cost).collect())
+    }
+
+    pub fn cost_to(&self, graph:&JunctionGraph, node:usize) -> Option<u32> {
+        self.arrivals(graph, node).0
+    }
+
+    // one of the cheapest paths as corridors, None if the node was not reached
+    #[allow(dead_code)]
+    pub fn path_to(&self, graph:&JunctionGraph, node:usize) -> Option<Vec<usize>> {
+        let (cost, arriving) = self.arrivals(graph, node);
+        cost?;
+        let mut path:Vec<usize> = arriving.first().copied().into_iter().collect();
+        while let Some(&previous) = path.last().and_then(|&corridor| self.predecessors[corridor].first()) {
+            path.push(previous);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    // the corridors which are part of any cheapest path to the node, sorted
+    pub fn corridors_on_cheapest_paths(&self, graph:&JunctionGraph, node:usize) -> Vec<usize> {
+        let mut on_path = vec![false; graph.corridors.len()];
+        let mut backlog = self.arrivals(graph, node).1;
+        while let Some(corridor) = backlog.pop() {
+            if on_path[corridor] { continue; }
+            on_path[corridor] = true;
+            backlog.extend(&self.predecessors[corridor]);
+        }
+        (0..on_path.len()).filter(|&corridor| on_path[corridor]).collect()
+    }
+}
+
+#[test]
+fn test_junction_graph() {
+    use std::collections::HashSet;
+    let maze = PixelMap::<char>::from_strings(
+"#########
+#S....#E#
+#.###.#.#
+#.....#.#
+###.###.#
+#.......#
+#########".split('\n'));
+    let start = maze.find_first('S').unwrap();
+    let end = maze.find_first('E').unwrap();
+    let graph = junction_graph(&maze, |c| c != '#', [start, end]);
+    // S, E, the junctions at (3,3) and (3,5) and the dead end at (1,5), the bends are no nodes
+    assert_eq!(graph.nodes, [Point(1,1), Point(7,1), Point(3,3), Point(1,5), Point(3,5)]);
+    assert_eq!(graph.node_at(Point(3,3)), Some(2));
+    assert_eq!(graph.node_at(Point(1,3)), None);
+    assert_eq!(graph.corridors.iter().map(|corridor| corridor.length).sum::<usize>(), 2 * 24);
+    let right_of_start = graph.corridors.iter().find(|corridor| corridor.from == 0).unwrap();
+    assert_eq!(*right_of_start, Corridor {
+        from:0, to:2, length:8, turns:2, first:Right, last:Left,
+        cells:vec![Point(2,1), Point(3,1), Point(4,1), Point(5,1), Point(5,2), Point(5,3), Point(4,3), Point(3,3)]
+    });
+
+    // the same lengths as a breadth first search
+    let field = distance_field(&maze, [start], |_, to| maze[to] != '#');
+    let paths = graph.cheapest_paths(0, |_, corridor| corridor.length as u32);
+    for (node, &pos) in graph.nodes.iter().enumerate() {
+        assert_eq!(paths.cost_to(&graph, node), field.distances[pos]);
+    }
+    let path = paths.path_to(&graph, 1).unwrap();
+    let positions = graph.positions(&path);
+    assert_eq!(positions.len() as u32, field.distances[end].unwrap() + 1);
+    assert_eq!((positions[0], positions[positions.len() - 1]), (start, end));
+    assert!(positions.windows(2).all(|pair| pair[0].manhattan(pair[1]) == 1));
+    assert_eq!(paths.path_to(&graph, 0), Some(vec![]));
+
+    // turns in the corridors and at the nodes cost 100
+    let turn_cost = |before:Direction, after:Direction| if before == after { 0 } else { 100 };
+    let paths = graph.cheapest_paths(0, |previous, corridor|
+        corridor.length as u32 + 100 * corridor.turns as u32 + previous.map_or(0, |previous| turn_cost(previous.last, corridor.first)));
+    assert_eq!(paths.cost_to(&graph, 1), Some(14 + 4 * 100));
+    assert_eq!(graph.positions(&paths.path_to(&graph, 1).unwrap())[1], Point(1,2));
+
+    // counting corridors, both ways to (3,3) are cheapest
+    let paths = graph.cheapest_paths(0, |_, _| 1);
+    assert_eq!(paths.cost_to(&graph, 1), Some(3));
+    let tiles:HashSet<Position> = paths.corridors_on_cheapest_paths(&graph, 1).iter()
+        .flat_map(|&corridor| graph.positions(&[corridor]))
+        .collect();
+    assert_eq!(tiles.len(), 24 - 2);
+    assert!(!tiles.contains(&Point(2,5)));
+}
+
+//////////////////////////////////////////
+/// Rays
+//////////////////////////////////////////
+// all rays start with the start position and end at the edge
+impl<E:Copy+PartialEq> PixelMap<E> {
+    pub fn ray(&self, start:Position, direction:Direction) -> impl Iterator<Item=(Position, E)> + '_ {
+        self.area.ray(start, direction).map(|pos| (pos, self[pos]))
+    }
+
+    // None if no pixel on the ray matches
+    pub fn first_in_ray(&self, start:Position, direction:Direction, predicate:impl Fn(E) -> bool) -> Option<Position> {
+        self.ray(start, direction).find(|&(_, element)| predicate(element)).map(|(pos, _)| pos)
+    }
+
+    // the start and the following pixels equal to it
+    #[allow(dead_code)]
+    pub fn run_in_ray(&self, start:Position, direction:Direction) -> impl Iterator<Item=Position> + '_ {
+        let first = self[start];
+        self.ray(start, direction).take_while(move |&(_, element)| element == first).map(|(pos, _)| pos)
+    }
+}
+
+#[test]
+fn test_rays() {
+    let area = Area::new(4, 3);
+    assert_eq!(area.ray(Point(1,1), Right).collect::<Vec<_>>(), [Point(1,1), Point(2,1), Point(3,1)]);
+    assert_eq!(area.ray(Point(1,1), UpLeft).collect::<Vec<_>>(), [Point(1,1), Point(0,0)]);
+    assert_eq!(area.ray(Point(0,2), Down).collect::<Vec<_>>(), [Point(0,2)]);
+    // line of sight without the start
+    assert_eq!(area.ray(Point(3,0), Left).skip(1).count(), 3);
+    let torus = Area::toroidal(4, 3);
+    assert_eq!(torus.ray(Point(1,1), Up).collect::<Vec<_>>(), [Point(1,1), Point(1,0), Point(1,2)]);
+    assert_eq!(torus.ray(Point(1,1), DownRight).count(), 12);
+
+    let map = PixelMap::<char>::from_strings(
+"#..#
+.OO.
+..#.".split('\n'));
+    assert_eq!(map.ray(Point(0,1), Right).map(|(_, c)| c).collect::<String>(), ".OO.");
+    assert_eq!(map.first_in_ray(Point(1,1), Right, |c| c != 'O'), Some(Point(3,1)));
+    assert_eq!(map.first_in_ray(Point(0,1), Right, |c| c == '#'), None);
+    assert_eq!(map.first_in_ray(Point(0,2), UpRight, |c| c == '#'), None);
+    assert_eq!(map.first_in_ray(Point(2,2), Up, |c| c == '#'), Some(Point(2,2)));
+    assert_eq!(map.run_in_ray(Point(1,1), Right).collect::<Vec<_>>(), [Point(1,1), Point(2,1)]);
+    assert_eq!(map.run_in_ray(Point(1,0), Right).count(), 2);
+    assert_eq!(map.run_in_ray(Point(3,1), Down).count(), 2);
+}
+
+//////////////////////////////////////////
+/// Patterns
+//////////////////////////////////////////
+// a template is a small map where None matches every pixel
+pub type Template<E> = PixelMap<Option<E>>;
+
+impl<E:TryFromChar+Copy+PartialEq> Template<E> {
+    pub fn template_from_strings<'a>(lines:impl Iterator<Item=&'a str>, wildcard:char) -> Self {
+        let lines:Vec<&str> = lines.collect();
+        let width = lines.first().map_or(0, |line| line.chars().count());
+        let mut template = PixelMap::new(width, lines.len(), None);
+        for (y, line) in lines.iter().enumerate() {
+            assert_eq!(line.chars().count(), width, "Could not create template, all rows must have the same length");
+            for (x, c) in line.chars().enumerate() {
+                if c != wildcard { template[Point(x, y)] = Some(E::from_char(c)); }
+            }
+        }
+        template
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Orientations {
+    Fixed, // only the template as it is
+    All // all different rotations and reflections of the template
+}
+
+impl<E:Copy+PartialEq> Template<E> {
+    // the eight rotations and reflections without duplicates
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut orientations:Vec<Self> = Vec::new();
+        for mut template in [self.clone(), self.flip_horizontal()] {
+            for _ in 0..4 {
+                if !orientations.contains(&template) { orientations.push(template.clone()); }
+                template = template.rotate_right();
+            }
+        }
+        orientations
+    }
+}
+
+// FNV-1a, much faster than the default hasher for single pixels
+struct PixelHasher(u64);
+
+impl Hasher for PixelHasher {
+    fn finish(&self) -> u64 { self.0 }
+    fn write(&mut self, bytes:&[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+fn pixel_hash<E:Hash>(element:&E) -> u64 {
+    let mut hasher = PixelHasher(0xcbf29ce484222325);
+    element.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<E:Copy+PartialEq+Hash> PixelMap<E> {
+    // the top left positions of all matches in reading order
+    // with Orientations::All a position is listed once for every orientation matching there
+    pub fn find_pattern(&self, template:&Template<E>, orientations:Orientations) -> Vec<Position> {
+        let hashes:Vec<u64> = self.pixels.iter().map(pixel_hash).collect();
+        let templates = match orientations {
+            Orientations::Fixed => vec![template.clone()],
+            Orientations::All => template.orientations()
+        };
+        let mut matches:Vec<Position> = templates.iter().flat_map(|template| self.find_template(template, &hashes)).collect();
+        matches.sort_by_key(|pos| (pos.1, pos.0));
+        matches
+    }
+
+    // Rabin-Karp on the longest run of fixed pixels in a template row, every hit is verified
+    fn find_template(&self, template:&Template<E>, hashes:&[u64]) -> Vec<Position> {
+        const BASE:u64 = 1_000_003;
+        let (width, height) = (self.area.width, self.area.height);
+        if template.area.width > width || template.area.height > height { return Vec::new(); }
+        let origins = Area::new(width - template.area.width + 1, height - template.area.height + 1);
+        let matches_at = |origin:Position| template.area.all_positions().all(|pos| {
+            template[pos].is_none_or(|element| self[Point(origin.0 + pos.0, origin.1 + pos.1)] == element)
+        });
+
+        // (start, length) of the anchor
+        let mut anchor = (Point(0, 0), 0);
+        for y in 0..template.area.height {
+            let mut start = 0;
+            for x in 0..=template.area.width {
+                if x < template.area.width && template[Point(x, y)].is_some() { continue; }
+                if x - start > anchor.1 { anchor = (Point(start, y), x - start); }
+                start = x + 1;
+            }
+        }
+        let (anchor_pos, length) = anchor;
+        if length == 0 {
+            return origins.all_positions().collect();
+        }
+
+        let power = (1..length).fold(1u64, |power, _| power.wrapping_mul(BASE));
+        let rolling = |hash:u64, element_hash:u64| hash.wrapping_mul(BASE).wrapping_add(element_hash);
+        let anchor_hash = (0..length).fold(0, |hash, i| rolling(hash, pixel_hash(&template[Point(anchor_pos.0 + i, anchor_pos.1)].unwrap())));
+
+        let mut matches = Vec::new();
+        for origin_y in 0..origins.height {
+            let row = &hashes[(origin_y + anchor_pos.1) * width..][..width];
+            let mut hash = row[..length].iter().fold(0, |hash, &element_hash| rolling(hash, element_hash));
+            for x in 0..=width - length {
+                if x > 0 {
+                    hash = rolling(hash.wrapping_sub(row[x - 1].wrapping_mul(power)), row[x + length - 1]);
+                }
+                if hash != anchor_hash || x < anchor_pos.0 { continue; }
+                let origin = Point(x - anchor_pos.0, origin_y);
+                if origin.0 < origins.width && matches_at(origin) {
+                    matches.push(origin);
+                }
+            }
+        }
+        matches
+    }
+}
+
+#[test]
+fn test_patterns() {
+    let map = PixelMap::<char>::from_strings(
+"M.S.M
+.A.A.
+M.S.M
+.....".split('\n'));
+    let xmas = Template::<char>::template_from_strings("M?S\n?A?\nM?S".split('\n'), '?');
+    assert_eq!(xmas[Point(1,1)], Some('A'));
+    assert_eq!(xmas[Point(1,0)], None);
+    assert_eq!(xmas.orientations().len(), 4);
+    assert_eq!(map.find_pattern(&xmas, Orientations::Fixed), [Point(0,0)]);
+    assert_eq!(map.find_pattern(&xmas, Orientations::All), [Point(0,0), Point(2,0)]);
+    assert_eq!(map.find_pattern(&xmas.rotate_180(), Orientations::Fixed), [Point(2,0)]);
+
+    let row = Template::<char>::template_from_strings(["..."].into_iter(), '?');
+    assert_eq!(row.orientations().len(), 2);
+    assert_eq!(map.find_pattern(&row, Orientations::Fixed), [Point(0,3), Point(1,3), Point(2,3)]);
+    assert_eq!(map.find_pattern(&row, Orientations::All).len(), 3);
+    assert_eq!(map.find_pattern(&row.transpose(), Orientations::Fixed), []);
+    assert_eq!(map.find_pattern(&PixelMap::new(1, 2, Some('.')), Orientations::Fixed), [Point(1,2), Point(3,2)]);
+    // wildcards at the border of the template
+    let corner = Template::<char>::template_from_strings("??\n?A".split('\n'), '?');
+    assert_eq!(map.find_pattern(&corner, Orientations::Fixed), [Point(0,0), Point(2,0)]);
+    let anything = Template::<char>::template_from_strings("???".split('\n'), '?');
+    assert_eq!(map.find_pattern(&anything, Orientations::Fixed).len(), 3 * 4);
+    assert_eq!(map.find_pattern(&PixelMap::new(6, 1, None), Orientations::Fixed), []);
+
+    // all eight orientations are different
+    let l = Template::<char>::template_from_strings("AB\nC?".split('\n'), '?');
+    assert_eq!(l.orientations().len(), 8);
+    let abc = PixelMap::<char>::from_strings("ABA\nCBC".split('\n'));
+    assert_eq!(abc.find_pattern(&l, Orientations::All), [Point(0,0), Point(1,0)]);
+}
+
+//////////////////////////////////////////
+/// SVG
+//////////////////////////////////////////
+// a vector drawing over a grid, for results that are lines and shapes rather than pixels
+// positions are drawn at the centre of their pixel, outlines go through pixel corners like in outlines()
+pub struct Svg {
+    area:Area,
+    cell_size:usize,
+    elements:Vec<String>
+}
+
+// the characters with a meaning in XML
+fn escape_xml(text:&str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl Svg {
+    pub fn new(area:&Area, cell_size:usize) -> Svg {
+        assert!(cell_size > 0, "The cell size must be at least 1");
+        Svg { area:area.clone(), cell_size, elements:Vec::new() }
+    }
+
+    fn centre(&self, pos:Position) -> (f64, f64) {
+        let size = self.cell_size as f64;
+        ((pos.0 as f64 + 0.5) * size, (pos.1 as f64 + 0.5) * size)
+    }
+
+    pub fn cell(mut self, pos:Position, fill:&str) -> Self {
+        self.elements.push(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            pos.0 * self.cell_size, pos.1 * self.cell_size, self.cell_size, self.cell_size, escape_xml(fill)));
+        self
+    }
+
+    pub fn line(mut self, from:Position, to:Position, color:&str) -> Self {
+        let ((x1, y1), (x2, y2)) = (self.centre(from), self.centre(to));
+        self.elements.push(format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\"/>", x1, y1, x2, y2, escape_xml(color)));
+        self
+    }
+
+    // a closed outline through the given corners
+    pub fn polygon(mut self, corners:&[Position], color:&str) -> Self {
+        let points:Vec<String> = corners.iter().map(|corner| format!("{},{}", corner.0 * self.cell_size, corner.1 * self.cell_size)).collect();
+        self.elements.push(format!("<polygon points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>", points.join(" "), escape_xml(color)));
+        self
+    }
+
+    // a dot with the label next to it
+    pub fn point(mut self, pos:Position, label:&str, color:&str) -> Self {
+        let (x, y) = self.centre(pos);
+        let radius = self.cell_size as f64 / 4.0;
+        self.elements.push(format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>", x, y, radius, escape_xml(color)));
+        if !label.is_empty() {
+            self.elements.push(format!("<text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"{}\">{}</text>",
+                x + radius, y - radius, self.cell_size, escape_xml(color), escape_xml(label)));
+        }
+        self
+    }
+
+    // every element on its own line, e.g. for a file
+    pub fn write_to(&self, mut writer:impl Write) -> std::io::Result<()> {
+        writeln!(writer, "{}", self)
+    }
+
+    // writes frames/{name}.svg
+    pub fn save(&self, name:&str) -> std::io::Result<()> {
+        std::fs::create_dir_all("frames")?;
+        self.write_to(std::io::BufWriter::new(std::fs::File::create(format!("frames/{}.svg", name))?))
+    }
+}
+
+// the document with a white background and a light grid line between all pixels
+impl fmt::Display for Svg {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        let (width, height) = (self.area.width * self.cell_size, self.area.height * self.cell_size);
+        writeln!(f, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", width, height, width, height)?;
+        writeln!(f, "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>", width, height)?;
+        for x in 0..=self.area.width {
+            writeln!(f, "<line x1=\"{}\" y1=\"0\" x2=\"{}\" y2=\"{}\" stroke=\"lightgray\"/>", x * self.cell_size, x * self.cell_size, height)?;
+        }
+        for y in 0..=self.area.height {
+            writeln!(f, "<line x1=\"0\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"lightgray\"/>", y * self.cell_size, width, y * self.cell_size)?;
+        }
+        for element in &self.elements {
+            writeln!(f, "{}", element)?;
+        }
+        write!(f, "</svg>")
+    }
+}
+
+#[test]
+fn test_svg() {
+    let svg = Svg::new(&Area::new(2, 1), 10)
+        .cell(Point(1,0), "red")
+        .line(Point(0,0), Point(1,0), "blue")
+        .polygon(&[Point(0,0), Point(2,0), Point(2,1), Point(0,1)], "green")
+        .point(Point(1,0), "a<b", "black")
+        .point(Point(0,0), "", "black");
+    let text = svg.to_string();
+    let lines:Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"10\" viewBox=\"0 0 20 10\">");
+    // background, three vertical and two horizontal grid lines
+    assert_eq!(lines[1..7].iter().filter(|line| line.contains("lightgray")).count(), 5);
+    assert_eq!(lines[7], "<rect x=\"10\" y=\"0\" width=\"10\" height=\"10\" fill=\"red\"/>");
+    assert_eq!(lines[8], "<line x1=\"5\" y1=\"5\" x2=\"15\" y2=\"5\" stroke=\"blue\"/>");
+    assert_eq!(lines[9], "<polygon points=\"0,0 20,0 20,10 0,10\" fill=\"none\" stroke=\"green\" stroke-width=\"2\"/>");
+    assert_eq!(lines[10], "<circle cx=\"15\" cy=\"5\" r=\"2.5\" fill=\"black\"/>");
+    assert_eq!(lines[11], "<text x=\"17.5\" y=\"2.5\" font-size=\"10\" fill=\"black\">a&lt;b</text>");
+    // no text without a label
+    assert!(lines[12].starts_with("<circle"));
+    assert_eq!(lines[13], "</svg>");
+
+    let mut written = Vec::new();
+    svg.write_to(&mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), text + "\n");
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum TestEnum {
+    A,
+    B,
+    C
+}
+
+impl TryFromChar for char {
+    fn try_from_char(c:char) -> Option<Self> { Some(c) }
+}
+
+impl TryFromChar for TestEnum {
+    fn try_from_char(c:char) -> Option<Self> {
+        match c {
+            'A' => Some(TestEnum::A),
+            'B' => Some(TestEnum::B),
+            'C' => Some(TestEnum::C),
+            _ => None
+        }
+    }
+}
+
+impl ToChar for TestEnum {
+    fn to_char(self) -> char {
+        match self {
+            TestEnum::A => 'A',
+            TestEnum::B => 'B',
+            TestEnum::C => 'C'
+        }
+    }
+}
+
+#[test]
+fn test_text() {
+    type TestMap = PixelMap<TestEnum>;
+    let text = "ABC\nBCA";
+    let map:TestMap = text.parse().unwrap();
+    assert_eq!(map.to_string(), text);
+    assert_eq!(map.to_string_lines(), ["ABC", "BCA"]);
+    assert_eq!(map.to_string().parse::<TestMap>(), Ok(map.clone()));
+
+    let mut file = Vec::new();
+    map.write_to(&mut file).unwrap();
+    assert_eq!(String::from_utf8(file).unwrap(), "ABC\nBCA\n");
+    assert_eq!("ABC\nBCA\n".parse::<TestMap>(), Ok(map.clone()));
+
+    assert_eq!(map.rotate_right().to_string(), "BA\nCB\nAC");
+    assert_eq!(TestMap::new(0, 0, TestEnum::A).to_string(), "");
+    assert_eq!("AB\nAD".parse::<TestMap>().unwrap_err().to_string(), "line 2, column 2: unexpected character 'D' for year2024::maps::TestEnum");
+}
+
+#[test]
+fn test_from_strings() {
+    type TestMap = PixelMap<TestEnum>;
+    let input =
+"ABC
+BCA";
+    let pixel_map = TestMap::from_strings(input.split('\n'));
+    assert_eq!(pixel_map.area, Area::new(3, 2));
+    assert_eq!(pixel_map.width(), 3);
+    assert_eq!(pixel_map.height(), 2);
+    assert_eq!(pixel_map.row(0), [TestEnum::A, TestEnum::B, TestEnum::C]);
+    assert_eq!(pixel_map.row(1), [TestEnum::B, TestEnum::C, TestEnum::A]);
+    assert_eq!(pixel_map.column(2).collect::<Vec<_>>(), [&TestEnum::C, &TestEnum::A]);
+    assert_eq!(pixel_map.at(Point(1,1)), TestEnum::C);
+    assert_eq!(pixel_map[Point(2,0)], TestEnum::C);
+    assert_eq!(pixel_map.area.all_positions().map( |pos| pixel_map.at(pos) ).collect::<Vec<_>>(), vec![
+        TestEnum::A, TestEnum::B, TestEnum::C,
+        TestEnum::B, TestEnum::C, TestEnum::A
+    ]);
+
+    let mut pixel_map = pixel_map;
+    pixel_map[Point(0,1)] = TestEnum::C;
+    pixel_map.row_mut(0)[1] = TestEnum::A;
+    assert_eq!(pixel_map, TestMap::from_strings(["AAC", "CCA"].into_iter()));
+    assert_eq!(TestMap::new(2, 3, TestEnum::B).column(1).count(), 3);
+}
+
+#[test]
+#[should_panic(expected = "Point(3, 0) is outside of the map")]
+fn test_pixel_map_outside() {
+    // would be Point(0, 1) without the check
+    PixelMap::<TestEnum>::from_strings(["ABC", "BCA"].into_iter()).at(Point(3, 0));
+}
+
+// the two ways to store the pixels, for test_storage_timing
+#[cfg(test)]
+trait Storage<E:Copy> {
+    fn create(width:usize, height:usize, value:E) -> Self;
+    fn read(&self, position:Position) -> E;
+    fn write(&mut self, position:Position, value:E);
+}
+
+#[cfg(test)]
+impl<E:Copy+PartialEq> Storage<E> for PixelMap<E> {
+    fn create(width:usize, height:usize, value:E) -> Self { PixelMap::new(width, height, value) }
+    fn read(&self, position:Position) -> E { self[position] }
+    fn write(&mut self, position:Position, value:E) { self[position] = value; }
+}
+
+#[cfg(test)]
+impl<E:Copy> Storage<E> for Vec<Vec<E>> {
+    fn create(width:usize, height:usize, value:E) -> Self { vec![vec![value; width]; height] }
+    fn read(&self, position:Position) -> E { self[position.1][position.0] }
+    fn write(&mut self, position:Position, value:E) { self[position.1][position.0] = value; }
+}
+
+// the cost map of day 20: the distance of every track position to the end
+#[cfg(test)]
+fn timing_cost_map<M:Storage<char>, C:Storage<Option<u32>>>(chars:&PixelMap<char>) -> u64 {
+    let area = &chars.area;
+    let mut map = M::create(area.width, area.height, '.');
+    area.all_positions().for_each(|pos| map.write(pos, chars[pos]));
+    let mut costs = C::create(area.width, area.height, None);
+    let end = chars.find_first('E').unwrap();
+    costs.write(end, Some(0));
+    let mut backlog = VecDeque::from([end]);
+    while let Some(pos) = backlog.pop_front() {
+        let cost = costs.read(pos).unwrap() + 1;
+        for next in area.neighbors4(pos) {
+            if map.read(next) != '#' && costs.read(next).is_none() {
+                costs.write(next, Some(cost));
+                backlog.push_back(next);
+            }
+        }
+    }
+    area.all_positions().filter_map(|pos| costs.read(pos)).map(u64::from).sum()
+}
+
+// the regions of day 12: label every plot with the region of equal plants around it
+#[cfg(test)]
+fn timing_regions<M:Storage<char>, L:Storage<usize>>(chars:&PixelMap<char>) -> u64 {
+    const UNLABELLED:usize = usize::MAX;
+    let area = &chars.area;
+    let mut map = M::create(area.width, area.height, '.');
+    area.all_positions().for_each(|pos| map.write(pos, chars[pos]));
+    let mut labels = L::create(area.width, area.height, UNLABELLED);
+    let mut count = 0;
+    for start in area.all_positions() {
+        if labels.read(start) != UNLABELLED { continue; }
+        labels.write(start, count);
+        let mut backlog = vec![start];
+        while let Some(pos) = backlog.pop() {
+            for next in area.neighbors4(pos) {
+                if labels.read(next) == UNLABELLED && map.read(pos) == map.read(next) {
+                    labels.write(next, count);
+                    backlog.push(next);
+                }
+            }
+        }
+        count += 1;
+    }
+    area.all_positions().map(|pos| labels.read(pos) as u64).sum()
+}
+
+// compares the flat storage with one Vec per row on the inputs of day 20 and day 12, run with
+// cargo test --release test_storage_timing -- --ignored --nocapture
+#[test]
+#[ignore]
+fn test_storage_timing() {
+    use std::time::{Duration, Instant};
+    use crate::helper::read_file_to_string;
+    fn time(rounds:u32, workload:impl Fn() -> u64) -> (u64, Duration) {
+        let start = Instant::now();
+        let result = (0..rounds).map(|_| workload()).last().unwrap();
+        (result, start.elapsed() / rounds)
+    }
+    let rounds = 50;
+    let racetrack = PixelMap::<char>::from_strings(read_file_to_string("input/day20.txt").lines());
+    let garden = PixelMap::<char>::from_strings(read_file_to_string("input/day12.txt").lines());
+    type Workload = fn(&PixelMap<char>) -> u64;
+    let workloads:[(&str, &PixelMap<char>, Workload, Workload); 2] = [
+        ("day 20 cost map", &racetrack, timing_cost_map::<PixelMap<_>, PixelMap<_>>, timing_cost_map::<Vec<Vec<_>>, Vec<Vec<_>>>),
+        ("day 12 regions", &garden, timing_regions::<PixelMap<_>, PixelMap<_>>, timing_regions::<Vec<Vec<_>>, Vec<Vec<_>>>)
+    ];
+    for (name, chars, flat, nested) in workloads {
+        let (flat_result, flat_time) = time(rounds, || flat(chars));
+        let (nested_result, nested_time) = time(rounds, || nested(chars));
+        assert_eq!(flat_result, nested_result);
+        println!("{}: flat Vec<E> {:?}, Vec<Vec<E>> {:?}", name, flat_time, nested_time);
+    }
+}
+
+#[test]
+fn test_try_from_strings() {
+    type TestMap = PixelMap<TestEnum>;
+    assert_eq!(TestMap::try_from_strings("ABC\nBCA".lines()), Ok(TestMap::from_strings(["ABC", "BCA"].into_iter())));
+    assert_eq!(TestMap::try_from_strings("".lines()).map(|map| map.area), Ok(Area::new(0, 0)));
+
+    let error = TestMap::try_from_strings("ABC\nBXA".lines()).unwrap_err();
+    assert_eq!(error.to_string(), "line 2, column 2: unexpected character 'X' for year2024::maps::TestEnum");
+    assert_eq!(TestMap::try_from_strings("ABC\nBC\nABC".lines()).unwrap_err().to_string(), "line 2, column 3: row has 2 characters instead of 3");
+    assert_eq!(TestMap::try_from_strings("AB\nBCA".lines()).unwrap_err().to_string(), "line 2, column 3: row has 3 characters instead of 2");
+
+    assert_eq!(Direction::try_from_char('v'), Some(Down));
+    assert_eq!(Direction::try_from_char('x'), None);
+}
+
+#[test]
+#[should_panic(expected = "row has 1 characters instead of 3")]
+fn test_from_strings_ragged() {
+    PixelMap::<char>::from_strings(["abc", "d"].into_iter());
+}
@@ -229,6 +229,7 @@ impl<P:Problem> ProblemSolver<'_, P> {
 }
 
 // find one path with lowest cost to an end state
+#[allow(dead_code)]
 pub fn get_cost_of_state<P:Problem>(problem:&P, start_state:P::State) -> Cost where <P as Problem>::Action: 'static {
 
     let mut solver = ProblemSolver::new(problem, start_state);
@@ -242,6 +243,7 @@ pub fn get_cost_of_state<P:Problem>(problem:&P, start_state:P::State) -> Cost wh
     }
 }
 
+#[allow(dead_code)]
 pub fn get_cost_cache<P:Problem>(problem:&P, start_state:P::State) -> CostCache<P::State> where <P as Problem>::Action: 'static {
 
     let mut solver = ProblemSolver::new(problem, start_state);
@@ -0,0 +1,186 @@
+// Terminal rendering of maps
+//
+// A Renderer draws a PixelMap with overlays on top of it, later overlays win.
+// ANSI colours are only used if stdout is a terminal, otherwise the characters are printed plain.
+
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+
+use crate::maps::{Direction, PixelMap, Position, ToChar};
+
+//////////////////////////////////////////
+/// Style
+//////////////////////////////////////////
+
+// the eight standard terminal colours, not every day uses all of them
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White
+}
+
+impl Color {
+    fn ansi_offset(self) -> u8 {
+        match self {
+            Color::Black   => 0,
+            Color::Red     => 1,
+            Color::Green   => 2,
+            Color::Yellow  => 3,
+            Color::Blue    => 4,
+            Color::Magenta => 5,
+            Color::Cyan    => 6,
+            Color::White   => 7
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Style {
+    pub foreground:Option<Color>,
+    pub background:Option<Color>,
+    pub character:Option<char> // replaces the character of the pixel
+}
+
+impl Style {
+    pub fn foreground(color:Color) -> Style {
+        Style { foreground:Some(color), ..Style::default() }
+    }
+
+    pub fn background(color:Color) -> Style {
+        Style { background:Some(color), ..Style::default() }
+    }
+
+    pub fn with_character(self, character:char) -> Style {
+        Style { character:Some(character), ..self }
+    }
+
+    // the fields set in {other} win
+    fn merge(self, other:Style) -> Style {
+        Style {
+            foreground:other.foreground.or(self.foreground),
+            background:other.background.or(self.background),
+            character:other.character.or(self.character)
+        }
+    }
+
+    // e.g. "\x1b[31;42m", empty without colours
+    fn ansi_start(&self) -> String {
+        let codes:Vec<String> = [self.foreground.map(|color| 30 + color.ansi_offset()), self.background.map(|color| 40 + color.ansi_offset())]
+            .iter().flatten().map(|code| code.to_string()).collect();
+        if codes.is_empty() { String::new() } else { format!("\x1b[{}m", codes.join(";")) }
+    }
+}
+
+const ANSI_RESET:&str = "\x1b[0m";
+
+//////////////////////////////////////////
+/// Renderer
+//////////////////////////////////////////
+type Overlay<'a, E> = Box<dyn Fn(Position, E) -> Option<Style> + 'a>;
+
+pub struct Renderer<'a, E> {
+    map:&'a PixelMap<E>,
+    overlays:Vec<Overlay<'a, E>>
+}
+
+impl<'a, E:ToChar+Copy+PartialEq+'a> Renderer<'a, E> {
+    pub fn new(map:&'a PixelMap<E>) -> Self {
+        Renderer { map, overlays:Vec::new() }
+    }
+
+    pub fn highlight(mut self, positions:impl IntoIterator<Item=Position>, style:Style) -> Self {
+        let positions:HashSet<Position> = positions.into_iter().collect();
+        self.overlays.push(Box::new(move |pos, _| if positions.contains(&pos) { Some(style) } else { None }));
+        self
+    }
+
+    // every step is drawn as an arrow where it starts, the last position only gets the colours
+    // the path ends at the edge of the map
+    pub fn path(mut self, start:Position, directions:impl IntoIterator<Item=Direction>, style:Style) -> Self {
+        let mut arrows:HashMap<Position, Style> = HashMap::new();
+        let mut pos = start;
+        for direction in directions {
+            arrows.insert(pos, style.with_character(direction.to_char()));
+            match self.map.area.step(pos, direction) {
+                Some(next) => { pos = next; },
+                None => { break; }
+            }
+        }
+        arrows.entry(pos).or_insert(style);
+        self.overlays.push(Box::new(move |pos, _| arrows.get(&pos).copied()));
+        self
+    }
+
+    // a style for every pixel, None keeps the pixel as it is
+    pub fn colors(mut self, style_at:impl Fn(Position, E) -> Option<Style> + 'a) -> Self {
+        self.overlays.push(Box::new(style_at));
+        self
+    }
+
+    fn style_at(&self, pos:Position) -> Style {
+        let element = self.map[pos];
+        self.overlays.iter().filter_map(|overlay| overlay(pos, element)).fold(Style::default(), Style::merge)
+    }
+
+    // one line per row, each ending with a newline
+    pub fn render(&self, ansi:bool) -> String {
+        let mut text = String::new();
+        for y in 0..self.map.area.height {
+            for x in 0..self.map.area.width {
+                let pos = crate::maps::Point(x, y);
+                let style = self.style_at(pos);
+                let c = style.character.unwrap_or(self.map[pos].to_char());
+                let start = if ansi { style.ansi_start() } else { String::new() };
+                if start.is_empty() {
+                    text.push(c);
+                } else {
+                    text.push_str(&format!("{}{}{}", start, c, ANSI_RESET));
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn println(&self) {
+        print!("{}", self.render(std::io::stdout().is_terminal()));
+    }
+}
+
+#[test]
+fn test_render() {
+    use crate::maps::Point;
+    use Direction::*;
+    let map = PixelMap::<char>::from_strings("#...\n#.#.".split('\n'));
+
+    let plain = Renderer::new(&map);
+    assert_eq!(plain.render(true), "#...\n#.#.\n");
+
+    let highlighted = Renderer::new(&map).highlight([Point(1,0), Point(3,1)], Style::foreground(Color::Red));
+    assert_eq!(highlighted.render(false), "#...\n#.#.\n");
+    assert_eq!(highlighted.render(true), "#\x1b[31m.\x1b[0m..\n#.#\x1b[31m.\x1b[0m\n");
+
+    // the later overlays win, but only for the fields they set
+    let walls = |_, c| if c == '#' { Some(Style::background(Color::Blue)) } else { None };
+    let path = Renderer::new(&map)
+        .colors(walls)
+        .highlight([Point(0,0)], Style::foreground(Color::Yellow).with_character('W'))
+        .path(Point(1,1), [Up, Right, Right, Down, Down], Style::foreground(Color::Green));
+    assert_eq!(path.render(false), "W>>v\n#^#v\n");
+    let short = Renderer::new(&map).path(Point(1,1), [Up, Right], Style::foreground(Color::Green));
+    assert_eq!(short.render(false), "#>..\n#^#.\n");
+    assert_eq!(short.render(true).lines().next().unwrap(), "#\x1b[32m>\x1b[0m\x1b[32m.\x1b[0m.");
+    assert_eq!(path.render(true).lines().next().unwrap(), "\x1b[33;44mW\x1b[0m\x1b[32m>\x1b[0m\x1b[32m>\x1b[0m\x1b[32mv\x1b[0m");
+    assert_eq!(path.render(true).lines().nth(1).unwrap(), "\x1b[44m#\x1b[0m\x1b[32m^\x1b[0m\x1b[44m#\x1b[0m\x1b[32mv\x1b[0m");
+
+    // the ANSI palette starts with black
+    assert_eq!(Style::foreground(Color::White).merge(Style::background(Color::Black)).ansi_start(), "\x1b[37;40m");
+    assert_eq!(Style::foreground(Color::Magenta).merge(Style::background(Color::Cyan)).ansi_start(), "\x1b[35;46m");
+}
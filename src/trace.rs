@@ -0,0 +1,308 @@
+// Trace recording of simulations
+//
+// Each step of a simulation is written as one JSON object per line (JSON Lines).
+// Recording is enabled with --trace <file>, the replay command steps through the file.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::maps::{Direction, PixelMap, Position, ToChar};
+
+//////////////////////////////////////////
+/// Event
+//////////////////////////////////////////
+
+// one step of a simulation, only the fields which are set are written
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Event {
+    pub day:u32,
+    pub step:usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position:Option<(usize, usize)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    direction:Option<String>,
+    // the whole map, the following events only contain the changed cells
+    #[serde(skip_serializing_if = "Option::is_none")]
+    map:Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changed_cells:Option<Vec<(usize, usize, char)>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    robots:Option<Vec<(usize, usize)>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registers:Option<BTreeMap<String, i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instruction:Option<(String, i64)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output:Option<i64>
+}
+
+impl Event {
+    // step 0 is the state before the first step
+    pub fn new(day:u32, step:usize) -> Self {
+        Event { day, step, ..Default::default() }
+    }
+
+    pub fn position(self, position:Position) -> Self {
+        Event { position:Some((position.0, position.1)), ..self }
+    }
+
+    pub fn direction(self, direction:Direction) -> Self {
+        Event { direction:Some(format!("{:?}", direction)), ..self }
+    }
+
+    pub fn map<E:ToChar+Clone+Copy>(self, map:&PixelMap<E>) -> Self {
+        Event { map:Some(map.to_string_lines()), ..self }
+    }
+
+    // cells of the map which changed in this step with their new content
+    pub fn changed_cells(self, cells:&[(Position, char)]) -> Self {
+        Event { changed_cells:Some(cells.iter().map(|&(position, c)| (position.0, position.1, c)).collect()), ..self }
+    }
+
+    pub fn robots(self, robots:impl Iterator<Item=Position>) -> Self {
+        Event { robots:Some(robots.map(|position| (position.0, position.1)).collect()), ..self }
+    }
+
+    pub fn registers(self, registers:&[(&str, i64)]) -> Self {
+        Event { registers:Some(registers.iter().map(|&(name, value)| (name.to_string(), value)).collect()), ..self }
+    }
+
+    pub fn instruction(self, opcode:String, operand:i64) -> Self {
+        Event { instruction:Some((opcode, operand)), ..self }
+    }
+
+    pub fn output(self, output:i64) -> Self {
+        Event { output:Some(output), ..self }
+    }
+
+    // short description for the replay, e.g. "day 6, step 3, position (4,5), direction Up"
+    pub fn describe(&self) -> String {
+        let mut texts = vec![format!("day {}", self.day), format!("step {}", self.step)];
+        if let Some((x, y)) = self.position { texts.push(format!("position ({},{})", x, y)); }
+        if let Some(direction) = &self.direction { texts.push(format!("direction {}", direction)); }
+        if let Some(cells) = &self.changed_cells {
+            texts.push(format!("changed cells [{}]", cells.iter().map(|(x, y, c)| format!("({},{}) {}", x, y, c)).collect::<Vec<_>>().join(", ")));
+        }
+        if let Some(robots) = &self.robots { texts.push(format!("{} robots", robots.len())); }
+        if let Some(registers) = &self.registers {
+            texts.push(format!("registers {}", registers.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join(" ")));
+        }
+        if let Some((opcode, operand)) = &self.instruction { texts.push(format!("instruction {} {}", opcode, operand)); }
+        if let Some(output) = self.output { texts.push(format!("output {}", output)); }
+        texts.join(", ")
+    }
+}
+
+#[test]
+fn test_event() {
+    use crate::maps::Point;
+    let event = Event::new(6, 3).position(Point(4,5)).direction(Direction::Up).changed_cells(&[(Point(1,2), 'O')]);
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(json, r#"{"day":6,"step":3,"position":[4,5],"direction":"Up","changed_cells":[[1,2,"O"]]}"#);
+    assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+    assert_eq!(event.describe(), "day 6, step 3, position (4,5), direction Up, changed cells [(1,2) O]");
+
+    let event = Event::new(17, 0).registers(&[("a", 729), ("ip", 0)]).instruction("ADV".to_string(), 3);
+    assert_eq!(serde_json::to_string(&event).unwrap(), r#"{"day":17,"step":0,"registers":{"a":729,"ip":0},"instruction":["ADV",3]}"#);
+    assert_eq!(event.describe(), "day 17, step 0, registers a=729 ip=0, instruction ADV 3");
+}
+
+//////////////////////////////////////////
+/// Recorder
+//////////////////////////////////////////
+// writes the events of the simulations, does nothing without --trace
+pub struct Recorder<'a> {
+    writer:Option<Box<dyn Write + 'a>>
+}
+
+impl<'a> Recorder<'a> {
+    pub fn disabled() -> Self {
+        Recorder { writer:None }
+    }
+
+    pub fn new(writer:impl Write + 'a) -> Self {
+        Recorder { writer:Some(Box::new(writer)) }
+    }
+
+    // checked before an event is created, so there are no costs without --trace
+    pub fn enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn record(&mut self, event:Event) {
+        if let Some(writer) = &mut self.writer {
+            serde_json::to_writer(&mut *writer, &event).unwrap_or_else(|err| panic!("Could not write trace: {}", err));
+            writeln!(writer).unwrap_or_else(|err| panic!("Could not write trace: {}", err));
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush().unwrap_or_else(|err| panic!("Could not write trace: {}", err));
+        }
+    }
+}
+
+impl Recorder<'static> {
+    pub fn create(filename:&str) -> Self {
+        let file = File::create(filename).unwrap_or_else(|err| panic!("Could not create {}: {}", filename, err));
+        Recorder::new(BufWriter::new(file))
+    }
+}
+
+#[test]
+fn test_recorder() {
+    use crate::maps::Point;
+    let mut trace = Vec::new();
+    let mut recorder = Recorder::new(&mut trace);
+    assert!(recorder.enabled());
+    recorder.record(Event::new(6, 0).position(Point(4,6)));
+    recorder.record(Event::new(6, 1).position(Point(4,5)));
+    recorder.finish();
+    assert!(!recorder.enabled());
+    drop(recorder);
+    assert_eq!(String::from_utf8(trace).unwrap(), "{\"day\":6,\"step\":0,\"position\":[4,6]}\n{\"day\":6,\"step\":1,\"position\":[4,5]}\n");
+
+    let mut recorder = Recorder::disabled();
+    assert!(!recorder.enabled());
+    recorder.record(Event::new(6, 0));
+}
+
+//////////////////////////////////////////
+/// Replay
+//////////////////////////////////////////
+pub struct Replay {
+    events:Vec<Event>,
+    index:usize
+}
+
+impl Replay {
+    pub fn read<R:BufRead>(reader:R) -> Result<Replay, String> {
+        let mut events = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| format!("line {}: {}", index + 1, err))?;
+            if line.trim().is_empty() { continue; }
+            events.push(serde_json::from_str::<Event>(&line).map_err(|err| format!("line {}: {}", index + 1, err))?);
+        }
+        Ok(Replay { events, index:0 })
+    }
+
+    pub fn current(&self) -> Option<&Event> {
+        self.events.get(self.index)
+    }
+
+    // "n" or an empty line: next event, "b": previous event, a number: go to that event, "q": quit
+    // return false to quit
+    pub fn command(&mut self, command:&str) -> Result<bool, String> {
+        match command.trim() {
+            "" | "n" => { if self.index + 1 < self.events.len() { self.index += 1; } },
+            "b"      => { self.index = self.index.saturating_sub(1); },
+            "q"      => { return Ok(false); },
+            number   => {
+                let event = number.parse::<usize>().map_err(|_| format!("unknown command '{}', use n, b, q or an event number", number))?;
+                if event == 0 || event > self.events.len() {
+                    return Err(format!("there are only events 1 to {}", self.events.len()));
+                }
+                self.index = event - 1;
+            }
+        }
+        Ok(true)
+    }
+
+    // the map at the current event, rebuilt from the last full map and the changed cells since then
+    // so going back or jumping to any event shows the right map
+    pub fn map(&self) -> Option<Vec<String>> {
+        let events = &self.events[..=self.index.min(self.events.len().checked_sub(1)?)];
+        let start = events.iter().rposition(|event| event.map.is_some())?;
+        let mut rows:Vec<Vec<char>> = events[start].map.as_ref().unwrap().iter().map(|row| row.chars().collect()).collect();
+        for &(x, y, c) in events[start + 1..].iter().filter_map(|event| event.changed_cells.as_ref()).flatten() {
+            if let Some(cell) = rows.get_mut(y).and_then(|row| row.get_mut(x)) {
+                *cell = c;
+            }
+        }
+        Some(rows.into_iter().map(|row| row.into_iter().collect()).collect())
+    }
+
+    pub fn describe(&self) -> String {
+        let Some(event) = self.current() else {
+            return "no events".to_string();
+        };
+        let mut text = format!("[{}/{}] {}", self.index + 1, self.events.len(), event.describe());
+        for row in self.map().unwrap_or_default() {
+            text.push('\n');
+            text.push_str(&row);
+        }
+        text
+    }
+}
+
+#[test]
+fn test_replay() {
+    let trace =
+r#"{"day":6,"step":0,"position":[4,6],"direction":"Up"}
+{"day":6,"step":1,"position":[4,5],"direction":"Up"}
+
+{"day":6,"step":2,"position":[4,5],"direction":"Right"}
+"#;
+    let mut replay = Replay::read(trace.as_bytes()).unwrap();
+    assert_eq!(replay.describe(), "[1/3] day 6, step 0, position (4,6), direction Up");
+    assert_eq!(replay.command("b"), Ok(true));
+    assert_eq!(replay.current().unwrap().step, 0);
+    assert_eq!(replay.command(""), Ok(true));
+    assert_eq!(replay.command("n"), Ok(true));
+    assert_eq!(replay.command("n"), Ok(true));
+    assert_eq!(replay.describe(), "[3/3] day 6, step 2, position (4,5), direction Right");
+    assert_eq!(replay.command("b"), Ok(true));
+    assert_eq!(replay.current().unwrap().step, 1);
+    assert_eq!(replay.command("3"), Ok(true));
+    assert_eq!(replay.current().unwrap().direction, Some("Right".to_string()));
+    assert_eq!(replay.command("4"), Err("there are only events 1 to 3".to_string()));
+    assert_eq!(replay.command("q"), Ok(false));
+    assert_eq!(replay.map(), None);
+
+    assert!(Replay::read("{\"day\":6}\n[1,".as_bytes()).err().unwrap().starts_with("line 2: "));
+}
+
+#[test]
+fn test_replay_map() {
+    // a box pushed right twice
+    let trace =
+r##"{"day":15,"step":0,"map":["#....#","#@O..#"]}
+{"day":15,"step":1,"changed_cells":[[1,1,"."],[2,1,"@"],[3,1,"O"]]}
+{"day":15,"step":2,"changed_cells":[[2,1,"."],[3,1,"@"],[4,1,"O"]]}
+"##;
+    let mut replay = Replay::read(trace.as_bytes()).unwrap();
+    assert_eq!(replay.map(), Some(vec!["#....#".to_string(), "#@O..#".to_string()]));
+    replay.command("3").unwrap();
+    assert_eq!(replay.map(), Some(vec!["#....#".to_string(), "#..@O#".to_string()]));
+    // going back undoes the changed cells
+    replay.command("b").unwrap();
+    assert_eq!(replay.describe(), "[2/3] day 15, step 1, changed cells [(1,1) ., (2,1) @, (3,1) O]\n#....#\n#.@O.#");
+    replay.command("b").unwrap();
+    assert_eq!(replay.map(), Some(vec!["#....#".to_string(), "#@O..#".to_string()]));
+}
+
+// step through the trace with commands from stdin
+pub fn replay(filename:&str) {
+    let file = File::open(filename).unwrap_or_else(|_| panic!("Could not open {}", filename));
+    let mut replay = Replay::read(BufReader::new(file)).unwrap_or_else(|err| panic!("Could not read {}, {}", filename, err));
+    if replay.current().is_none() {
+        println!("{} contains no events", filename);
+        return;
+    }
+    println!("Replay of {}: <enter> or n = next, b = back, <number> = go to event, q = quit", filename);
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        println!("{}", replay.describe());
+        let Some(Ok(command)) = lines.next() else { return };
+        match replay.command(&command) {
+            Ok(true)  => {},
+            Ok(false) => return,
+            Err(err)  => println!("{}", err)
+        }
+    }
+}